@@ -0,0 +1,240 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+// generates the sliding attack tables and the KPK bitbase into OUT_DIR, bitboard.rs and kpk.rs
+// include_bytes! them. the attack tables come from an independent ray-walker, the tests in
+// bitboard.rs check every table entry against naive_bishop_attack/naive_rook_attack.
+
+include!("src/data/magics.rs");
+include!("src/data/kpk.rs");
+
+/* ==== sliding attacks ==== */
+
+const BISHOP_DIRECTIONS: [(isize, isize); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+const ROOK_DIRECTIONS: [(isize, isize); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+// squares are indexed row * 8 + col, see bitboard.rs
+fn on_board(row: isize, col: isize) -> bool {
+    (0..8).contains(&row) && (0..8).contains(&col)
+}
+
+// attacked squares, a ray stops at (and includes) the first blocker
+fn slider_attack(square: usize, blockers: u64, directions: &[(isize, isize)]) -> u64 {
+    let mut attack: u64 = 0;
+    for &(d_row, d_col) in directions {
+        let (mut row, mut col) = ((square / 8) as isize + d_row, (square % 8) as isize + d_col);
+        while on_board(row, col) {
+            let bit = 1u64 << (row * 8 + col);
+            attack |= bit;
+            if blockers & bit != 0 {
+                break;
+            }
+            row += d_row;
+            col += d_col;
+        }
+    }
+    attack
+}
+
+// squares whose occupancy matters, the last square of each ray never does
+fn relevant_mask(square: usize, directions: &[(isize, isize)]) -> u64 {
+    let mut mask: u64 = 0;
+    for &(d_row, d_col) in directions {
+        let (mut row, mut col) = ((square / 8) as isize + d_row, (square % 8) as isize + d_col);
+        while on_board(row + d_row, col + d_col) {
+            mask |= 1u64 << (row * 8 + col);
+            row += d_row;
+            col += d_col;
+        }
+    }
+    mask
+}
+
+// the index-th subset of mask
+fn occupancy(index: usize, mask: u64) -> u64 {
+    let mut mask = mask;
+    let mut occupancy: u64 = 0;
+    let mut i: usize = 0;
+    while mask != 0 {
+        let bit = mask & mask.wrapping_neg();
+        if index & (1 << i) != 0 {
+            occupancy |= bit;
+        }
+        mask ^= bit;
+        i += 1;
+    }
+    occupancy
+}
+
+fn attack_table(directions: &[(isize, isize)], magics: &[u64; 64], bitcounts: &[usize; 64], size: usize) -> Vec<u64> {
+    let mut table: Vec<u64> = vec![0; 64 * size];
+    for square in 0..64 {
+        let mask = relevant_mask(square, directions);
+        assert_eq!(mask.count_ones() as usize, bitcounts[square], "build.rs: index size mismatch on {}", square);
+        for j in 0..(1usize << bitcounts[square]) {
+            let blockers = occupancy(j, mask);
+            let attack = slider_attack(square, blockers, directions);
+            let m = (blockers.wrapping_mul(magics[square]) >> (64 - bitcounts[square])) as usize;
+            let entry = &mut table[square * size + m];
+            if *entry == 0 {
+                *entry = attack;
+            } else if *entry != attack {
+                panic!("build.rs: invalid magic collision on square {}!", square);
+            }
+        }
+    }
+    table
+}
+
+/* ==== king and pawn versus king ==== */
+
+// positions sharing a pawn square
+const BLOCK_SIZE: usize = 2 * 64 * 64;
+
+// results during generation, combined with bitwise or
+const INVALID: u8 = 0b000;
+const UNKNOWN: u8 = 0b001;
+const DRAW: u8 = 0b010;
+const WIN: u8 = 0b100;
+
+fn king_attack(square: usize) -> u64 {
+    let mut attack: u64 = 0;
+    for d_row in -1..=1 {
+        for d_col in -1..=1 {
+            let (row, col) = ((square / 8) as isize + d_row, (square % 8) as isize + d_col);
+            if (d_row, d_col) != (0, 0) && on_board(row, col) {
+                attack |= 1u64 << (row * 8 + col);
+            }
+        }
+    }
+    attack
+}
+
+// squares attacked by a white pawn
+fn pawn_attack(square: usize) -> u64 {
+    let (row, col) = ((square / 8) as isize + 1, (square % 8) as isize);
+    [col - 1, col + 1].iter().filter(|&&col| on_board(row, col)).fold(0, |attack, col| attack | 1u64 << (row * 8 + col))
+}
+
+fn distance(a: usize, b: usize) -> usize {
+    (a / 8).abs_diff(b / 8).max((a % 8).abs_diff(b % 8))
+}
+
+// inverse of index() in data/kpk.rs
+fn unpack(i: usize) -> (bool, usize, usize, usize) {
+    let file = (i >> 13) & 0b11;
+    let rank = (i >> 15) + 1;
+    (((i >> 12) & 1) == 1, i & 0b111111, (i >> 6) & 0b111111, rank * 8 + (7 - file))
+}
+
+// results that can be decided without looking at successors
+fn initial(strong_to_move: bool, strong_king: usize, weak_king: usize, pawn: usize) -> u8 {
+    let strong_attack = king_attack(strong_king);
+    let weak_attack = king_attack(weak_king);
+    if strong_king == weak_king
+        || strong_king == pawn
+        || weak_king == pawn
+        || distance(strong_king, weak_king) <= 1
+        || (strong_to_move && (pawn_attack(pawn) & (1 << weak_king)) != 0)
+    {
+        return INVALID;
+    }
+    // the pawn promotes and the new queen can't be taken
+    let push = pawn + 8;
+    if strong_to_move
+        && pawn / 8 == 6
+        && strong_king != push
+        && (distance(weak_king, push) > 1 || (strong_attack & (1 << push)) != 0)
+    {
+        return WIN;
+    }
+    // stalemate, or the weak king takes an undefended pawn
+    if !strong_to_move
+        && ((weak_attack & !(strong_attack | pawn_attack(pawn))) == 0
+            || (weak_attack & !strong_attack & (1 << pawn)) != 0)
+    {
+        return DRAW;
+    }
+    UNKNOWN
+}
+
+fn classify(db: &[u8], strong_to_move: bool, strong_king: usize, weak_king: usize, pawn: usize) -> u8 {
+    let (good, bad) = if strong_to_move { (WIN, DRAW) } else { (DRAW, WIN) };
+    let mut result: u8 = INVALID;
+    let mut moves = if strong_to_move { king_attack(strong_king) } else { king_attack(weak_king) };
+    while moves != 0 {
+        let target = moves.trailing_zeros() as usize;
+        result |= match strong_to_move {
+            true => db[index(false, target, weak_king, pawn)],
+            false => db[index(true, strong_king, target, pawn)],
+        };
+        moves &= moves - 1;
+    }
+    if strong_to_move && pawn / 8 < 6 {
+        let push = pawn + 8;
+        result |= db[index(false, strong_king, weak_king, push)];
+        if pawn / 8 == 1 && push != strong_king && push != weak_king {
+            result |= db[index(false, strong_king, weak_king, push + 8)];
+        }
+    }
+    if result & good != 0 {
+        good
+    } else if result & UNKNOWN != 0 {
+        UNKNOWN
+    } else {
+        bad
+    }
+}
+
+// one bit per position, set if the strong side wins
+fn kpk_bitbase() -> Vec<u64> {
+    let mut db: Vec<u8> = (0..KPK_SIZE)
+        .map(|i| {
+            let (strong_to_move, strong_king, weak_king, pawn) = unpack(i);
+            initial(strong_to_move, strong_king, weak_king, pawn)
+        })
+        .collect();
+    // pawn pushes only lead to higher ranks, so solve one pawn square at a time from rank 7 down.
+    // iterate until no unknown position can be resolved, whatever is left is a draw
+    for pawn_block in (0..KPK_SIZE / BLOCK_SIZE).rev() {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for i in pawn_block * BLOCK_SIZE..(pawn_block + 1) * BLOCK_SIZE {
+                if db[i] == UNKNOWN {
+                    let (strong_to_move, strong_king, weak_king, pawn) = unpack(i);
+                    let result = classify(&db, strong_to_move, strong_king, weak_king, pawn);
+                    if result != UNKNOWN {
+                        db[i] = result;
+                        changed = true;
+                    }
+                }
+            }
+        }
+    }
+    let mut bits: Vec<u64> = vec![0; KPK_SIZE / 64];
+    for (i, result) in db.iter().enumerate() {
+        if *result == WIN {
+            bits[i / 64] |= 1 << (i % 64);
+        }
+    }
+    bits
+}
+
+/* ==== output ==== */
+
+fn write_table(name: &str, table: &[u64]) {
+    let bytes: Vec<u8> = table.iter().flat_map(|x| x.to_le_bytes()).collect();
+    let path = Path::new(&env::var("OUT_DIR").unwrap()).join(name);
+    fs::write(path, bytes).unwrap();
+}
+
+fn main() {
+    println!("cargo::rerun-if-changed=build.rs");
+    println!("cargo::rerun-if-changed=src/data/magics.rs");
+    println!("cargo::rerun-if-changed=src/data/kpk.rs");
+    write_table("bishop_attacks.bin", &attack_table(&BISHOP_DIRECTIONS, &BISHOP_MAGICS, &BISHOP_OCC_BITCOUNT, 1 << 9));
+    write_table("rook_attacks.bin", &attack_table(&ROOK_DIRECTIONS, &ROOK_MAGICS, &ROOK_OCC_BITCOUNT, 1 << 12));
+    write_table("kpk.bin", &kpk_bitbase());
+}
@@ -0,0 +1,52 @@
+use chessbb::magic::*;
+
+// audits the hard-coded magic numbers, then searches for a fresh set
+// usage: cargo run --release --example magics -- [seed] [black]
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let seed: u64 = args.get(1).and_then(|x| x.parse().ok()).unwrap_or(0x5EED);
+    let kind = match args.get(2).map(|x| x.as_str()) {
+        Some("black") => MagicKind::Black,
+        _ => MagicKind::Plain,
+    };
+
+    for slider in [Slider::Bishop, Slider::Rook] {
+        let current = verify_magics(slider);
+        match total_table_size(&current) {
+            Some(size) => println!("{:?}: hard-coded magics are collision-free, minimal table size {}", slider, size),
+            None => {
+                for (square, entry) in current.iter().enumerate() {
+                    if entry.is_none() {
+                        println!("{:?}: hard-coded magic for square {} collides!", slider, square);
+                    }
+                }
+            }
+        }
+
+        let found = find_magics(slider, kind, seed, 100_000_000);
+        match total_table_size(&found) {
+            Some(size) => println!("{:?}: found {:?} magics with seed {}, minimal table size {}", slider, kind, seed, size),
+            None => println!("{:?}: search failed for some squares", slider),
+        }
+        print_magics(slider, &found);
+    }
+}
+
+fn print_magics(slider: Slider, entries: &[Option<MagicEntry>]) {
+    let name = match slider {
+        Slider::Bishop => "BISHOP_MAGICS",
+        Slider::Rook => "ROOK_MAGICS",
+    };
+    println!("const {}: [u64; 64] = [", name);
+    for row in entries.chunks(4) {
+        let row: Vec<String> = row
+            .iter()
+            .map(|entry| match entry {
+                Some(x) => format!("0x{:016X},", x.magic),
+                None => String::from("/* none */,"),
+            })
+            .collect();
+        println!("    {}", row.join(" "));
+    }
+    println!("];");
+}
@@ -1,21 +1,23 @@
 use std::fmt::Display;
-use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Index, Not};
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
 
+use crate::error::ChessError;
 use crate::square::Square;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub(crate) struct BitBoard {
+pub struct BitBoard {
     data: u64,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub(crate)enum Side {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Side {
     White,
     Black,
 }
 
 impl Side {
-    pub(crate) const fn update(&self) -> Self {
+    pub const fn update(&self) -> Self {
         match self {
             Side::White => Side::Black,
             Side::Black => Side::White,
@@ -24,7 +26,8 @@ impl Side {
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub(crate) enum PieceType {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PieceType {
     Pawn,
     Knight,
     Bishop,
@@ -126,19 +129,19 @@ impl Not for BitBoard {
 }
 
 impl BitBoard {
-    pub(crate) const fn new(data: u64) -> Self {
+    pub const fn new(data: u64) -> Self {
         Self { data }
     }
 
-    pub(crate) const ZERO: BitBoard = BitBoard { data: 0u64 };
-    pub(crate) const ONES: BitBoard = BitBoard { data: u64::MAX };
+    pub const ZERO: BitBoard = BitBoard { data: 0u64 };
+    pub const ONES: BitBoard = BitBoard { data: u64::MAX };
 
     //creates a bitboard with a a non-zero bit in the n-th place
     //pub(crate) const fn nth(n: usize) -> Self {
     //    Self { data: 1u64 << n }
     //}
 
-    pub(crate) const fn nth(sq: Square) -> Self {
+    pub const fn nth(sq: Square) -> Self {
         Self { data: 1u64 << sq.to_index() }
     }
 
@@ -165,31 +168,36 @@ impl BitBoard {
         self.data != 0u64
     }
 
+    #[allow(dead_code)]
     pub(crate)  fn set_bit(&mut self, i: usize) {
         self.data = self.data | 1u64 << i;
     }
 
+    #[allow(dead_code)]
     pub(crate) const fn get_bit(&self, i: usize) -> BitBoard {
         BitBoard {
             data: self.data & (1u64 << i),
         }
     }
 
-    pub(crate) const fn pop_bit(&self, square: Square) -> BitBoard {
+    pub const fn pop_bit(&self, square: Square) -> BitBoard {
         BitBoard {
             data: self.data & !(1u64 << square.to_index()),
         }
     }
 
+    #[allow(dead_code)]
     pub(crate) const fn get_bit_data(&self, i: usize) -> u64 {
         self.data & (1u64 << i)
     }
 
+    #[allow(dead_code)]
     pub(crate) const fn pop_bit_data(&self, i: usize) -> u64 {
         self.data & !(1u64 << i)
     }
 
     // index of least-significant-bit (lsb)
+    #[allow(dead_code)]
     pub(crate) const fn lsb_index(&self) -> Option<usize> {
         if self.data == 0u64 {
             return None;
@@ -199,7 +207,7 @@ impl BitBoard {
     }
 
     // square of least-significant-bit (lsb)
-    pub(crate) const fn lsb_square(&self) -> Option<Square> {
+    pub const fn lsb_square(&self) -> Option<Square> {
         if self.data == 0u64 {
             return None;
         } else {
@@ -207,7 +215,11 @@ impl BitBoard {
         }
     }
 
-    pub(crate) const fn count_ones(&self) -> u32 {
+    pub const fn to_u64(&self) -> u64 {
+        self.data
+    }
+
+    pub const fn count_ones(&self) -> u32 {
         self.data.count_ones()
     }
 
@@ -223,6 +235,7 @@ impl BitBoard {
         }
     }
 
+    #[allow(dead_code)]
     pub(crate) const fn bit_xor(&self, other: &BitBoard) -> BitBoard {
         BitBoard {
             data: self.data ^ other.data,
@@ -234,15 +247,202 @@ impl BitBoard {
     }
 }
 
+/* ==== iteration ==== */
+
+// iterates over the squares of a bitboard, from least-significant-bit to most-significant-bit
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SquareIter {
+    data: u64,
+}
+
+impl Iterator for SquareIter {
+    type Item = Square;
+
+    fn next(&mut self) -> Option<Square> {
+        if self.data == 0u64 {
+            return None;
+        }
+        let square = Square::new(self.data.trailing_zeros() as u8);
+        self.data &= self.data - 1;
+        Some(square)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.data.count_ones() as usize;
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for SquareIter {}
+
+impl IntoIterator for BitBoard {
+    type Item = Square;
+    type IntoIter = SquareIter;
+
+    fn into_iter(self) -> SquareIter {
+        SquareIter { data: self.data }
+    }
+}
+
+/* ==== shifts and geometry ==== */
+
+// note: COLS[0] is the h-file, so "east" (towards the h-file) shifts towards bit 0
+impl BitBoard {
+    pub const fn iter(&self) -> SquareIter {
+        SquareIter { data: self.data }
+    }
+
+    // square of most-significant-bit (msb)
+    pub const fn msb_square(&self) -> Option<Square> {
+        if self.data == 0u64 {
+            return None;
+        } else {
+            return Some(Square::new(63 - self.data.leading_zeros() as u8));
+        }
+    }
+
+    pub const fn north(&self) -> BitBoard {
+        BitBoard { data: self.data << 8 }
+    }
+
+    pub const fn south(&self) -> BitBoard {
+        BitBoard { data: self.data >> 8 }
+    }
+
+    pub const fn east(&self) -> BitBoard {
+        BitBoard { data: (self.data >> 1) & !COL_MASKS[7].data }
+    }
+
+    pub const fn west(&self) -> BitBoard {
+        BitBoard { data: (self.data << 1) & !COL_MASKS[0].data }
+    }
+
+    pub const fn north_east(&self) -> BitBoard {
+        BitBoard { data: (self.data << 7) & !COL_MASKS[7].data }
+    }
+
+    pub const fn north_west(&self) -> BitBoard {
+        BitBoard { data: (self.data << 9) & !COL_MASKS[0].data }
+    }
+
+    pub const fn south_east(&self) -> BitBoard {
+        BitBoard { data: (self.data >> 9) & !COL_MASKS[7].data }
+    }
+
+    pub const fn south_west(&self) -> BitBoard {
+        BitBoard { data: (self.data >> 7) & !COL_MASKS[0].data }
+    }
+
+    // rank 1 <-> rank 8
+    pub const fn flip_vertical(&self) -> BitBoard {
+        BitBoard { data: self.data.swap_bytes() }
+    }
+
+    // a-file <-> h-file
+    pub const fn mirror_horizontal(&self) -> BitBoard {
+        let mut data = self.data;
+        data = ((data >> 1) & 0x5555555555555555) | ((data & 0x5555555555555555) << 1);
+        data = ((data >> 2) & 0x3333333333333333) | ((data & 0x3333333333333333) << 2);
+        data = ((data >> 4) & 0x0F0F0F0F0F0F0F0F) | ((data & 0x0F0F0F0F0F0F0F0F) << 4);
+        BitBoard { data }
+    }
+
+    pub const fn rotate_180(&self) -> BitBoard {
+        BitBoard { data: self.data.reverse_bits() }
+    }
+
+    // a1 -> a8 -> h8 -> h1 -> a1
+    pub const fn rotate_clockwise(&self) -> BitBoard {
+        let mut data: u64 = 0u64;
+        let mut i: usize = 0;
+        while i < 64 {
+            if self.data & (1u64 << i) != 0 {
+                // (row, col) -> (col, 7 - row)
+                data |= 1u64 << (COLS[i] * 8 + (7 - ROWS[i]));
+            }
+            i += 1;
+        }
+        BitBoard { data }
+    }
+
+    // a1 -> h1 -> h8 -> a8 -> a1
+    pub const fn rotate_anticlockwise(&self) -> BitBoard {
+        let mut data: u64 = 0u64;
+        let mut i: usize = 0;
+        while i < 64 {
+            if self.data & (1u64 << i) != 0 {
+                // (row, col) -> (7 - col, row)
+                data |= 1u64 << ((7 - COLS[i]) * 8 + ROWS[i]);
+            }
+            i += 1;
+        }
+        BitBoard { data }
+    }
+
+    // rank of square
+    pub const fn row_mask(square: Square) -> BitBoard {
+        ROW_MASKS[ROWS[square.to_index()]]
+    }
+
+    // file of square
+    pub const fn col_mask(square: Square) -> BitBoard {
+        COL_MASKS[COLS[square.to_index()]]
+    }
+
+    pub const fn ddiag_mask(square: Square) -> BitBoard {
+        DDIAG_MASKS[DDIAG[square.to_index()]]
+    }
+
+    pub const fn adiag_mask(square: Square) -> BitBoard {
+        ADIAG_MASKS[ADIAG[square.to_index()]]
+    }
+
+    // squares strictly between a and b, empty if they are not on a common row, column or diagonal
+    pub const fn between(a: Square, b: Square) -> BitBoard {
+        if a.to_index() == b.to_index() {
+            return BitBoard::ZERO;
+        }
+        RAYS[a.to_index()][b.to_index()]
+    }
+
+    // the full row, column or diagonal through a and b, empty if there is none
+    pub const fn line(a: Square, b: Square) -> BitBoard {
+        if a.to_index() == b.to_index() {
+            return BitBoard::ZERO;
+        }
+        if is_same_row(a, b) {
+            return BitBoard::row_mask(a);
+        }
+        if is_same_col(a, b) {
+            return BitBoard::col_mask(a);
+        }
+        if is_same_ddiag(a, b) {
+            return BitBoard::ddiag_mask(a);
+        }
+        if is_same_adiag(a, b) {
+            return BitBoard::adiag_mask(a);
+        }
+        return BitBoard::ZERO;
+    }
+}
+
 const W_PAWN_ATTACKS: [BitBoard; 64] = pawn_attack(Side::White);
 const B_PAWN_ATTACKS: [BitBoard; 64] = pawn_attack(Side::Black);
 const KNIGHT_ATTACKS: [BitBoard; 64] = knight_attack();
 const KING_ATTACKS: [BitBoard; 64] = king_attack();
-const BISHOP_MBB_MASK: [BitBoard; 64] = bishop_mbb_mask();
-const ROOK_MBB_MASK: [BitBoard; 64] = rook_mbb_mask();
-const BISHOP_ATTACKS_MBB: [[BitBoard; 1 << 9]; 64] = BISHOP;
-const ROOK_ATTACKS_MBB: [[BitBoard; 1 << 12]; 64] = ROOK;
-pub(crate) const RAYS: [[BitBoard; 64]; 64] = rays();
+pub(crate) const BISHOP_MBB_MASK: [BitBoard; 64] = bishop_mbb_mask();
+pub(crate) const ROOK_MBB_MASK: [BitBoard; 64] = rook_mbb_mask();
+// the sliding attack tables are generated (and checked for collisions) at compile-time
+// generated by build.rs, which panics on a destructive magic collision
+static BISHOP_ATTACKS_MBB: [[BitBoard; SIZE_BISHOP]; 64] =
+    attack_table(include_bytes!(concat!(env!("OUT_DIR"), "/bishop_attacks.bin")));
+static ROOK_ATTACKS_MBB: [[BitBoard; SIZE_ROOK]; 64] =
+    attack_table(include_bytes!(concat!(env!("OUT_DIR"), "/rook_attacks.bin")));
+pub(crate) static RAYS: [[BitBoard; 64]; 64] = rays();
+pub(crate) const ROW_MASKS: [BitBoard; 8] = label_masks::<8>(ROWS);
+pub(crate) const COL_MASKS: [BitBoard; 8] = label_masks::<8>(COLS);
+pub(crate) const DDIAG_MASKS: [BitBoard; 15] = label_masks::<15>(DDIAG);
+pub(crate) const ADIAG_MASKS: [BitBoard; 15] = label_masks::<15>(ADIAG);
 
 const fn pawn_attack(side: Side) -> [BitBoard; 64] {
     let mut i: usize = 0;
@@ -371,7 +571,7 @@ const fn king_attack() -> [BitBoard; 64] {
     return attack_array;
 }
 
-const fn naive_bishop_attack(i: usize, blockers: BitBoard) -> BitBoard {
+pub(crate) const fn naive_bishop_attack(i: usize, blockers: BitBoard) -> BitBoard {
     let i_rank: isize = (i as isize) / 8isize;
     let i_file: isize = (i as isize) % 8isize;
     let mut j: isize = 1;
@@ -442,7 +642,7 @@ const fn naive_bishop_attack(i: usize, blockers: BitBoard) -> BitBoard {
     BitBoard { data }
 }
 
-const fn naive_rook_attack(i: usize, blockers: BitBoard) -> BitBoard {
+pub(crate) const fn naive_rook_attack(i: usize, blockers: BitBoard) -> BitBoard {
     let i_rank: isize = (i as isize) / 8isize; // row
     let i_file: isize = (i as isize) % 8isize; // collumn
     let mut data: u64 = 0u64;
@@ -573,7 +773,7 @@ const fn rook_mbb_mask() -> [BitBoard; 64] {
     return attack_array;
 }
 
-const fn compute_occ_bb(index: usize, mask_bitcount: usize, attack_mask: BitBoard) -> BitBoard {
+pub(crate) const fn compute_occ_bb(index: usize, mask_bitcount: usize, attack_mask: BitBoard) -> BitBoard {
     /* use pdep? */
     let mut attack_mask: BitBoard = attack_mask;
     let mut occupancy_bb: BitBoard = BitBoard::ZERO;
@@ -596,26 +796,15 @@ const fn compute_occ_bb(index: usize, mask_bitcount: usize, attack_mask: BitBoar
 const SIZE_BISHOP: usize = 1 << 9; //size of the index for bishop magic bitboard index in bits
 const SIZE_ROOK: usize = 1 << 12; //size of the index for rook magic bitboard index in bits
 
-const fn bishop_attack_mbb() -> [[BitBoard; SIZE_BISHOP]; 64] {
+// the tables are written by build.rs as little endian u64s, square by square
+const fn attack_table<const N: usize>(bytes: &[u8]) -> [[BitBoard; N]; 64] {
+    assert!(bytes.len() == 64 * N * 8, "attack_table error: generated table has the wrong size!");
+    let mut attacks: [[BitBoard; N]; 64] = [[BitBoard::ZERO; N]; 64];
     let mut i: usize = 0;
-    let mut attacks: [[BitBoard; 1 << 9]; 64] = [[BitBoard::ZERO; 1 << 9]; 64];
-    let bishop_mbb_mask = BISHOP_MBB_MASK;
-    let bishop_occ_bitcount = BISHOP_OCC_BITCOUNT;
     while i < 64 {
-        let mask = bishop_mbb_mask[i];
-        let bitcount = bishop_occ_bitcount[i];
-        let max_index: usize = 1 << bitcount;
-
         let mut j: usize = 0;
-        while j < max_index {
-            let blockers = compute_occ_bb(j, bitcount, mask);
-            let m = magic_index(BISHOP_MAGICS[i], blockers, bitcount);
-
-            if attacks[i][m].data == BitBoard::ZERO.data {
-                attacks[i][m] = naive_bishop_attack(i, blockers);
-            } else if attacks[i][m].data != naive_bishop_attack(i, blockers).data {
-                panic!("bishop_attack_mbb error: invalid colision!");
-            }
+        while j < N {
+            attacks[i][j] = BitBoard::new(read_u64(bytes, i * N + j));
             j += 1;
         }
         i += 1;
@@ -623,31 +812,22 @@ const fn bishop_attack_mbb() -> [[BitBoard; SIZE_BISHOP]; 64] {
     return attacks;
 }
 
-const fn rook_attack_mbb() -> [[BitBoard; SIZE_ROOK]; 64] {
+// the i-th little endian u64 of a table written by build.rs
+pub(crate) const fn read_u64(bytes: &[u8], i: usize) -> u64 {
+    let k = i * 8;
+    let b = [bytes[k], bytes[k + 1], bytes[k + 2], bytes[k + 3], bytes[k + 4], bytes[k + 5], bytes[k + 6], bytes[k + 7]];
+    return u64::from_le_bytes(b);
+}
+
+// one bitboard per label value, e.g. label_masks::<8>(ROWS)[r] is the r-th row
+const fn label_masks<const N: usize>(labels: [usize; 64]) -> [BitBoard; N] {
+    let mut masks: [BitBoard; N] = [BitBoard::ZERO; N];
     let mut i: usize = 0;
-    let mut attacks: [[BitBoard; 1 << 12]; 64] = [[BitBoard::ZERO; 1 << 12]; 64];
-    let rook_mbb_mask = ROOK_MBB_MASK;
-    let rook_occ_bitcount = ROOK_OCC_BITCOUNT;
     while i < 64 {
-        let mask = rook_mbb_mask[i];
-        let bitcount = rook_occ_bitcount[i];
-        let max_index: usize = 1 << bitcount;
-
-        let mut j: usize = 0;
-        while j < max_index {
-            let blockers = compute_occ_bb(j, bitcount, mask);
-            let m = magic_index(ROOK_MAGICS[i], blockers, bitcount);
-
-            if attacks[i][m].data == BitBoard::ZERO.data {
-                attacks[i][m] = naive_rook_attack(i, blockers);
-            } else if attacks[i][m].data != naive_rook_attack(i, blockers).data {
-                panic!("rook_attack_mbb error: invalid colision!");
-            }
-            j += 1;
-        }
+        masks[labels[i]].data |= 1u64 << i;
         i += 1;
     }
-    return attacks;
+    return masks;
 }
 
 const fn rays() -> [[BitBoard; 64]; 64] {
@@ -661,10 +841,10 @@ const fn rays() -> [[BitBoard; 64]; 64] {
             let data = (1u64 << i) | (1u64 << j);
             let squares = BitBoard { data };
             if (ROWS[i] == ROWS[j]) || (COLS[i] == COLS[j]) {
-                let data: u64 = const_get_rook_attack(i_square, squares).data & const_get_rook_attack(j_square, squares).data;
+                let data: u64 = magic_rook_attack(i_square, squares).data & magic_rook_attack(j_square, squares).data;
                 rays[i][j].data = data;
             } else if (DDIAG[i] == DDIAG[j]) || (ADIAG[i] == ADIAG[j]) {
-                let data = const_get_bishop_attack(i_square, squares).data & const_get_bishop_attack(j_square, squares).data;
+                let data = magic_bishop_attack(i_square, squares).data & magic_bishop_attack(j_square, squares).data;
                 rays[i][j].data = data;
             }
             j += 1;
@@ -675,94 +855,15 @@ const fn rays() -> [[BitBoard; 64]; 64] {
 }
 
 /* ==== magic ==== */
-include!("data/bishop.rs");
-include!("data/rook.rs");
-
-#[rustfmt::skip]
-const BISHOP_MAGICS: [u64; 64] = [
-    0x0140C80810488022, 0x0020021C01142000, 0x00308C2080200102, 0x0004040880000A09,
-    0x0824042080000001, 0x00C1010840807080, 0x810C010403200000, 0x49CE404044202081,
-    0x4405048410020200, 0x0000042104440080, 0x0801C12112008003, 0x0100080A43014001,
-    0x0000020210010000, 0x0110020110080990, 0x0800004804042000, 0x0000002434020800,
-    0x00C108E014890204, 0x0004040210440100, 0x4808001000801012, 0x0008004620801080,
-    0x0481000290400A01, 0x0001000180A00921, 0x1204010900A80492, 0x0A88400024041C00,
-    0x1002100088501014, 0x005045040818008C, 0x0002080081004408, 0x0208280005820002,
-    0x0509010040104008, 0x8010004000241000, 0x8908108440540400, 0x0142060800404240,
-    0x0231101010402410, 0x0002011140241020, 0x100A002A00101180, 0x2001010800110041,
-    0x8118022401224100, 0x4420092A40020800, 0x22D000C880031400, 0x000102108002A420,
-    0x4008044404102020, 0x8000842402002000, 0x000200242400080E, 0x0030004202208802,
-    0x0000011214000601, 0x10C0008099011081, 0x10080104608A0C00, 0x0002285D00202700,
-    0x009A182414050000, 0x020100A210223022, 0x0000002C02080102, 0x0000000020884010,
-    0x0280029002022040, 0x8250102490342010, 0x0040020464048080, 0x4120040102042200,
-    0x280A010401018800, 0x8010008084104200, 0x009009002484501A, 0x1A08830080420208,
-    0x2000064022604100, 0x0012400420044101, 0x0040042818810C00, 0x1024211464008200,
-];
-
-#[rustfmt::skip]
-const ROOK_MAGICS: [u64; 64] =  [
-    0x818001C000802018, 0xA240100020004000, 0x0100081041002000, 0x1080048010000800,
-    0x8600020020040810, 0x0580018002004400, 0x1080020000800100, 0x020000204A088401,
-    0x4000800080204000, 0x0040804000200080, 0x0000801000200080, 0x0222000C10204200,
-    0x0042000600081020, 0x00A2001004080200, 0x1000800100800200, 0x0082000092010044,
-    0x0800848000400420, 0x0030044040002001, 0x8000110041002004, 0x00004200200A0010,
-    0x0810808004000800, 0xC028808002000400, 0x0280040090080201, 0x0804020000508104,
-    0x0080400480088024, 0x0400200440100241, 0x0401001100200040, 0x0000100080800800,
-    0x0008010100041008, 0x8000020080800400, 0x1000012400024830, 0x0004008200210054,
-    0x08084A0082002100, 0x4080201000404000, 0xC000102001004100, 0x0004082101001002,
-    0x0009820800800400, 0x900C800400800200, 0x9040080204008150, 0x80B0140446000493,
-    0x6040244000828000, 0x0210002000504000, 0x0015002002110040, 0x0041001000210008,
-    0x0001004800050010, 0x0002000804010100, 0x5008081002040081, 0x00220040A1020004,
-    0x0101400120800180, 0x2040002000C08180, 0x1120001000480040, 0x18001020400A0200,
-    0x0004050010080100, 0x1023020080040080, 0x0001080102100400, 0x0001000282004300,
-    0x0190401100800021, 0x0805854001021021, 0x600010400C200101, 0x0010210009100005,
-    0x1001001002080005, 0x9801000C00080A29, 0x2006080A45029014, 0x0008804581022C02,
-];
-
-const BISHOP_OCC_BITCOUNT: [usize; 64] = [
-    6, 5, 5, 5, 5, 5, 5, 6, //
-    5, 5, 5, 5, 5, 5, 5, 5, //
-    5, 5, 7, 7, 7, 7, 5, 5, //
-    5, 5, 7, 9, 9, 7, 5, 5, //
-    5, 5, 7, 9, 9, 7, 5, 5, //
-    5, 5, 7, 7, 7, 7, 5, 5, //
-    5, 5, 5, 5, 5, 5, 5, 5, //
-    6, 5, 5, 5, 5, 5, 5, 6, //
-];
-
-const ROOK_OCC_BITCOUNT: [usize; 64] = [
-    12, 11, 11, 11, 11, 11, 11, 12, //
-    11, 10, 10, 10, 10, 10, 10, 11, //
-    11, 10, 10, 10, 10, 10, 10, 11, //
-    11, 10, 10, 10, 10, 10, 10, 11, //
-    11, 10, 10, 10, 10, 10, 10, 11, //
-    11, 10, 10, 10, 10, 10, 10, 11, //
-    11, 10, 10, 10, 10, 10, 10, 11, //
-    12, 11, 11, 11, 11, 11, 11, 12, //
-];
+include!("data/magics.rs");
 
 const fn magic_index(magic_num: u64, blockers: BitBoard, bitcount: usize) -> usize {
     ((blockers.data.wrapping_mul(magic_num)) >> (64 - bitcount)) as usize
 }
 
-const fn const_get_bishop_attack(square: Square, blockers: BitBoard) -> BitBoard {
-    let mask = BISHOP_MBB_MASK[square.to_index()];
-    let data = blockers.data & mask.data;
-    let blockers = BitBoard { data };
-    let m = magic_index(BISHOP_MAGICS[square.to_index()], blockers, BISHOP_OCC_BITCOUNT[square.to_index()]);
-    return BISHOP_ATTACKS_MBB[square.to_index()][m];
-}
-
-const fn const_get_rook_attack(square: Square, blockers: BitBoard) -> BitBoard {
-    let mask = ROOK_MBB_MASK[square.to_index()];
-    let data = blockers.data & mask.data;
-    let blockers = BitBoard { data };
-    let m = magic_index(ROOK_MAGICS[square.to_index()], blockers, ROOK_OCC_BITCOUNT[square.to_index()]);
-    return ROOK_ATTACKS_MBB[square.to_index()][m];
-}
-
 /* ==== constants and supporting functions ==== */
-const ASCII_SYM: [char; 12] = ['K', 'Q', 'N', 'B', 'R', 'P', 'k', 'q', 'n', 'b', 'r', 'p'];
-const UNICODE_SYM: [char; 12] = ['♚', '♛', '♞', '♝', '♜', '♟', '♔', '♕', '♘', '♗', '♖', '♙'];
+pub(crate) const ASCII_SYM: [char; 12] = ['K', 'Q', 'N', 'B', 'R', 'P', 'k', 'q', 'n', 'b', 'r', 'p'];
+pub(crate) const UNICODE_SYM: [char; 12] = ['♚', '♛', '♞', '♝', '♜', '♟', '♔', '♕', '♘', '♗', '♖', '♙'];
 
 pub(crate) const W_KING_SIDE_CASTLE_MASK: BitBoard =
     BitBoard::new(0b00000000_00000000_00000000_00000000_00000000_00000000_00000000_00000110);
@@ -796,7 +897,8 @@ pub const fn get_king_attack(square: Square) -> BitBoard {
     KING_ATTACKS[square.to_index()]
 }
 
-pub const fn get_bishop_attack(square: Square, blockers: BitBoard) -> BitBoard {
+// the magic bitboard lookups, also usable in const contexts
+pub const fn magic_bishop_attack(square: Square, blockers: BitBoard) -> BitBoard {
     let data = blockers.data & BISHOP_MBB_MASK[square.to_index()].data;
     let m = magic_index(
         BISHOP_MAGICS[square.to_index()],
@@ -806,7 +908,7 @@ pub const fn get_bishop_attack(square: Square, blockers: BitBoard) -> BitBoard {
     return BISHOP_ATTACKS_MBB[square.to_index()][m];
 }
 
-pub const fn get_rook_attack(square: Square, blockers: BitBoard) -> BitBoard {
+pub const fn magic_rook_attack(square: Square, blockers: BitBoard) -> BitBoard {
     let data = blockers.data & ROOK_MBB_MASK[square.to_index()].data;
     let m = magic_index(
         ROOK_MAGICS[square.to_index()],
@@ -816,9 +918,41 @@ pub const fn get_rook_attack(square: Square, blockers: BitBoard) -> BitBoard {
     return ROOK_ATTACKS_MBB[square.to_index()][m];
 }
 
-pub const fn get_queen_attack(square: Square, blockers: BitBoard) -> BitBoard {
-    BitBoard {
-        data: get_bishop_attack(square, blockers).data | get_rook_attack(square, blockers).data,
+// const fn, unless the bmi2 feature is on: the pext backend picks its lookup at runtime, so the
+// attack getters and the board queries built on them can only be const without it
+macro_rules! bmi2_const_fn {
+    ($(#[$attr:meta])* $vis:vis fn $($item:tt)*) => {
+        #[cfg(not(feature = "bmi2"))]
+        $(#[$attr])* $vis const fn $($item)*
+        #[cfg(feature = "bmi2")]
+        $(#[$attr])* $vis fn $($item)*
+    };
+}
+pub(crate) use bmi2_const_fn;
+
+// the bmi2 feature switches these to the pext backend, which itself falls back to the magic
+// lookups when the cpu has no bmi2
+bmi2_const_fn! {
+    pub fn get_bishop_attack(square: Square, blockers: BitBoard) -> BitBoard {
+        #[cfg(feature = "bmi2")]
+        return crate::pext::get_bishop_attack(square, blockers);
+        #[cfg(not(feature = "bmi2"))]
+        return magic_bishop_attack(square, blockers);
+    }
+}
+
+bmi2_const_fn! {
+    pub fn get_rook_attack(square: Square, blockers: BitBoard) -> BitBoard {
+        #[cfg(feature = "bmi2")]
+        return crate::pext::get_rook_attack(square, blockers);
+        #[cfg(not(feature = "bmi2"))]
+        return magic_rook_attack(square, blockers);
+    }
+}
+
+bmi2_const_fn! {
+    pub fn get_queen_attack(square: Square, blockers: BitBoard) -> BitBoard {
+        get_bishop_attack(square, blockers).bit_or(&get_rook_attack(square, blockers))
     }
 }
 pub const fn is_same_diag(source: Square, target: Square) -> bool {
@@ -862,17 +996,6 @@ pub(crate) const SQUARE_SYM: [&str; 64] = [
     "h8", "g8", "f8", "e8", "d8", "c8", "b8", "a8", //
 ];
 
-pub(crate) const SQUARE_SYM_REV: [&str; 64] = [
-    "a8", "b8", "c8", "d8", "e8", "f8", "g8", "h8", //
-    "a7", "b7", "c7", "d7", "e7", "f7", "g7", "h7", //
-    "a6", "b6", "c6", "d6", "e6", "f6", "g6", "h6", //
-    "a5", "b5", "c5", "d5", "e5", "f5", "g5", "h5", //
-    "a4", "b4", "c4", "d4", "e4", "f4", "g4", "h4", //
-    "a3", "b3", "c3", "d3", "e3", "f3", "g3", "h3", //
-    "a2", "b2", "c2", "d2", "e2", "f2", "g2", "h2", //
-    "a1", "b1", "c1", "d1", "e1", "f1", "g1", "h1", //
-];
-
 pub(crate) const RANK_CHAR: [char; 64] = [
     '1', '1', '1', '1', '1', '1', '1', '1', //
     '2', '2', '2', '2', '2', '2', '2', '2', //
@@ -993,6 +1116,13 @@ macro_rules! cpt_index {
     (p) => {(11)};
 }
 
+// inverse of cp_index
+#[rustfmt::skip]
+pub(crate) const CHESS_PIECES: [ChessPiece; 12] = [
+    cpt!(K), cpt!(Q), cpt!(N), cpt!(B), cpt!(R), cpt!(P),
+    cpt!(k), cpt!(q), cpt!(n), cpt!(b), cpt!(r), cpt!(p),
+];
+
 pub const fn cp_index(data: ChessPiece) -> usize {
     match data {
         cpt!(K) => 00,
@@ -1010,20 +1140,135 @@ pub const fn cp_index(data: ChessPiece) -> usize {
     }
 }
 
-pub const fn sym_index(c: char) -> usize {
+pub const fn try_sym_index(c: char) -> Result<usize, ChessError> {
     match c {
-        'K' => 0,
-        'Q' => 1,
-        'N' => 2,
-        'B' => 3,
-        'R' => 4,
-        'P' => 5,
-        'k' => 6,
-        'q' => 7,
-        'n' => 8,
-        'b' => 9,
-        'r' => 10,
-        'p' => 11,
-        _ => panic!("sym_index error: invalid char!"),
-    }
-}
\ No newline at end of file
+        'K' => Ok(0),
+        'Q' => Ok(1),
+        'N' => Ok(2),
+        'B' => Ok(3),
+        'R' => Ok(4),
+        'P' => Ok(5),
+        'k' => Ok(6),
+        'q' => Ok(7),
+        'n' => Ok(8),
+        'b' => Ok(9),
+        'r' => Ok(10),
+        'p' => Ok(11),
+        _ => Err(ChessError::InvalidPieceChar(c)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::square::sq;
+
+    // every relevant occupancy of every square must match the naive ray-walking attacks
+    #[test]
+    fn generated_bishop_attacks_match_naive() {
+        for i in 0..64usize {
+            let mask = BISHOP_MBB_MASK[i];
+            let bitcount = mask.count_ones() as usize;
+            assert_eq!(bitcount, BISHOP_OCC_BITCOUNT[i]);
+            for j in 0..(1usize << bitcount) {
+                let blockers = compute_occ_bb(j, bitcount, mask);
+                assert_eq!(get_bishop_attack(Square::new(i as u8), blockers), naive_bishop_attack(i, blockers));
+            }
+        }
+    }
+
+    #[test]
+    fn generated_rook_attacks_match_naive() {
+        for i in 0..64usize {
+            let mask = ROOK_MBB_MASK[i];
+            let bitcount = mask.count_ones() as usize;
+            assert_eq!(bitcount, ROOK_OCC_BITCOUNT[i]);
+            for j in 0..(1usize << bitcount) {
+                let blockers = compute_occ_bb(j, bitcount, mask);
+                assert_eq!(get_rook_attack(Square::new(i as u8), blockers), naive_rook_attack(i, blockers));
+            }
+        }
+    }
+
+    // blockers outside of the relevant mask (e.g. on the edges) must not change the attacks
+    #[test]
+    fn generated_attacks_ignore_irrelevant_blockers() {
+        for i in 0..64usize {
+            let square = Square::new(i as u8);
+            let bishop_edges = BISHOP_MBB_MASK[i].bit_not();
+            let rook_edges = ROOK_MBB_MASK[i].bit_not();
+            assert_eq!(get_bishop_attack(square, bishop_edges), naive_bishop_attack(i, BitBoard::ZERO));
+            assert_eq!(get_rook_attack(square, rook_edges), naive_rook_attack(i, BitBoard::ZERO));
+        }
+    }
+
+    #[test]
+    fn iterator_visits_every_square_once() {
+        let bitboard = BitBoard::new(0x8000_1000_0024_0001);
+        let squares: Vec<Square> = bitboard.into_iter().collect();
+        assert_eq!(squares.len(), bitboard.count_ones() as usize);
+        assert_eq!(squares.first().copied(), bitboard.lsb_square());
+        assert_eq!(squares.last().copied(), bitboard.msb_square());
+        let mut rebuilt = BitBoard::ZERO;
+        for square in bitboard {
+            rebuilt |= BitBoard::nth(square);
+        }
+        assert_eq!(rebuilt, bitboard);
+        assert_eq!(BitBoard::ZERO.msb_square(), None);
+    }
+
+    #[test]
+    fn shifts_do_not_wrap_around_files() {
+        let a_file = BitBoard::col_mask(sq("a1"));
+        let h_file = BitBoard::col_mask(sq("h1"));
+        assert_eq!(a_file.west(), BitBoard::ZERO);
+        assert_eq!(h_file.east(), BitBoard::ZERO);
+        assert_eq!(a_file.north_west(), BitBoard::ZERO);
+        assert_eq!(h_file.south_east(), BitBoard::ZERO);
+        assert_eq!(BitBoard::nth(sq("e4")).north(), BitBoard::nth(sq("e5")));
+        assert_eq!(BitBoard::nth(sq("e4")).east(), BitBoard::nth(sq("f4")));
+        assert_eq!(BitBoard::nth(sq("e4")).north_west(), BitBoard::nth(sq("d5")));
+        assert_eq!(BitBoard::nth(sq("e4")).south_east(), BitBoard::nth(sq("f3")));
+        assert_eq!(BitBoard::nth(sq("e4")).south_west(), BitBoard::nth(sq("d3")));
+        for i in 0..64u8 {
+            let square = Square::new(i);
+            assert_eq!(
+                BitBoard::nth(square).north_east().bit_or(&BitBoard::nth(square).north_west()),
+                get_w_pawn_attack(square)
+            );
+        }
+    }
+
+    #[test]
+    fn flips_and_rotations() {
+        let b2 = BitBoard::nth(sq("b2"));
+        assert_eq!(b2.flip_vertical(), BitBoard::nth(sq("b7")));
+        assert_eq!(b2.mirror_horizontal(), BitBoard::nth(sq("g2")));
+        assert_eq!(b2.rotate_180(), BitBoard::nth(sq("g7")));
+        assert_eq!(b2.rotate_clockwise(), BitBoard::nth(sq("b7")));
+        assert_eq!(b2.rotate_anticlockwise(), BitBoard::nth(sq("g2")));
+        let bitboard = BitBoard::new(0x0123_4567_89AB_CDEF);
+        assert_eq!(bitboard.rotate_clockwise().rotate_anticlockwise(), bitboard);
+        assert_eq!(bitboard.rotate_clockwise().rotate_clockwise(), bitboard.rotate_180());
+        assert_eq!(bitboard.flip_vertical().mirror_horizontal(), bitboard.rotate_180());
+    }
+
+    #[test]
+    fn between_and_line() {
+        assert_eq!(BitBoard::between(sq("a1"), sq("a4")), BitBoard::nth(sq("a2")) | BitBoard::nth(sq("a3")));
+        assert_eq!(BitBoard::between(sq("c1"), sq("f4")), BitBoard::nth(sq("d2")) | BitBoard::nth(sq("e3")));
+        assert_eq!(BitBoard::between(sq("a1"), sq("b3")), BitBoard::ZERO);
+        assert_eq!(BitBoard::between(sq("e4"), sq("e4")), BitBoard::ZERO);
+        assert_eq!(BitBoard::line(sq("b1"), sq("b5")), BitBoard::col_mask(sq("b8")));
+        assert_eq!(BitBoard::line(sq("a8"), sq("h1")).count_ones(), 8);
+        assert_eq!(BitBoard::line(sq("a1"), sq("b3")), BitBoard::ZERO);
+        for a in 0..64u8 {
+            for b in 0..64u8 {
+                let (a, b) = (Square::new(a), Square::new(b));
+                let line = BitBoard::line(a, b);
+                assert_eq!(BitBoard::between(a, b).bit_and(&line.bit_not()), BitBoard::ZERO);
+                assert_eq!(line.is_zero(), BitBoard::between(a, b).is_zero() && get_king_attack(a).nth_is_zero(b));
+            }
+        }
+    }
+}
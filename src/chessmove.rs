@@ -1,8 +1,8 @@
-use std::fmt::{Debug, Display};
-use std::ops::{Index, Not};
+use std::fmt::Display;
 
 use crate::PieceType;
-use crate::bitboard::*;
+use crate::bitboard::SQUARE_SYM;
+use crate::error::ChessError;
 use crate::square::Square;
 
 /* indexing the 64-squares:
@@ -38,7 +38,7 @@ note: promoted piece data are encoded as follows
 
 //API traits: Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display, Default
 
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ChessMove {
     data: u16,
 }
@@ -58,8 +58,22 @@ pub struct ChessMove {
 //    }
 //}
 
+// uci format, e.g. e2e4, or e7e8q for a queen promotion
+impl Display for ChessMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", SQUARE_SYM[self.source()], SQUARE_SYM[self.target()])?;
+        match self.move_type() {
+            MoveType::Promotion(PieceType::Knight) => write!(f, "n"),
+            MoveType::Promotion(PieceType::Bishop) => write!(f, "b"),
+            MoveType::Promotion(PieceType::Rook) => write!(f, "r"),
+            MoveType::Promotion(_) => write!(f, "q"),
+            _ => Ok(()),
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub(crate) enum MoveType {
+pub enum MoveType {
     Normal,
     Castle,
     EnPassant,
@@ -76,6 +90,15 @@ impl ChessMove {
         ((self.data & 0b111111_000000u16) as usize) >> 6
     }
 
+    // raw encoding, every u16 decodes to some move
+    pub const fn to_u16(&self) -> u16 {
+        self.data
+    }
+
+    pub const fn from_u16(data: u16) -> Self {
+        Self { data }
+    }
+
     //pub(crate) const fn piece_data(&self) -> Option<PieceType> {
     //    if let MoveType::Promotion = self.move_type() {
     //        match ((self.data & 0b11_000000_000000u16) as usize) >> 12 {
@@ -90,7 +113,7 @@ impl ChessMove {
     //    }
     //}
 
-    pub(crate) const fn move_type(&self) -> MoveType {
+    pub const fn move_type(&self) -> MoveType {
         let piece: PieceType = match ((self.data & 0b11_000000_000000u16) as usize) >> 12 {
             0b00 => PieceType::Knight,
             0b01 => PieceType::Bishop,
@@ -109,12 +132,12 @@ impl ChessMove {
     }
 
     /* set functions */
-    pub(crate) const fn set_source(&mut self, index: usize) {
-        self.data &= ((index << 0) & 0b111111) as u16;
+    pub const fn set_source(&mut self, index: usize) {
+        self.data = (self.data & !0b111111u16) | ((index as u16) & 0b111111);
     }
 
-    pub(crate) const fn set_target(&mut self, index: usize) {
-        self.data &= ((index << 6) & 0b111111_000000) as u16;
+    pub const fn set_target(&mut self, index: usize) {
+        self.data = (self.data & !0b111111_000000u16) | (((index as u16) << 6) & 0b111111_000000);
     }
 
     //pub(crate) const fn set_piece_data(&mut self, piece_data: Option<PieceType>) {
@@ -211,6 +234,13 @@ impl ChessMove {
         Self { data }
     }
 
+    pub const fn try_new(s: Square, t: Square, m: MoveType) -> Result<Self, ChessError> {
+        match m {
+            MoveType::Promotion(PieceType::King | PieceType::Pawn) => Err(ChessError::InvalidPromotion),
+            _ => Ok(ChessMove::new(s, t, m)),
+        }
+    }
+
     pub(crate) const fn promotions(source: Square, target: Square) -> [ChessMove; 4] {
         return [
             ChessMove::new(source, target, MoveType::Promotion(PieceType::Queen)),
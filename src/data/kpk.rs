@@ -0,0 +1,13 @@
+// index layout of the KPK bitbase, shared with build.rs which generates it.
+// positions are seen from the strong side, which is white (the pawn moves towards rank 8).
+// pawns on the e-h files are mirrored onto the a-d files, so the index covers
+// 4 files * 6 ranks * 2 sides * 64 * 64 king squares = 196608 positions, one bit each.
+
+pub(crate) const KPK_SIZE: usize = 4 * 6 * 2 * 64 * 64;
+
+// pawn files are mirrored to the a-d files (cols 4-7, col 0 is the h-file) by the caller
+pub(crate) const fn index(strong_to_move: bool, strong_king: usize, weak_king: usize, pawn: usize) -> usize {
+    let file = 7 - pawn % 8;
+    let rank = pawn / 8 - 1;
+    strong_king | (weak_king << 6) | ((strong_to_move as usize) << 12) | (file << 13) | (rank << 15)
+}
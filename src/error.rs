@@ -0,0 +1,82 @@
+use std::fmt::Display;
+
+use crate::bitboard::{SQUARE_SYM, Side};
+use crate::square::Square;
+
+/* ==== errors ==== */
+
+// returned by the try_ variants of functions that would otherwise panic on an unexpected position,
+// the panicking versions are kept for hot paths where the position is known to be legal.
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ChessError {
+    KingNotFound(Side),
+    EmptySquare(Square),
+    KingAsPiece(Square),
+    KingGivesCheck(Square),
+    InvalidPieceChar(char),
+    InvalidPromotion,
+    InvalidSquare,
+    InvalidFen(&'static str),
+    InvalidEncoding(&'static str),
+}
+
+impl Display for ChessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChessError::KingNotFound(side) => write!(f, "{:?} king not found", side),
+            ChessError::EmptySquare(square) => write!(f, "{} is empty", SQUARE_SYM[square.to_index()]),
+            ChessError::KingAsPiece(square) => write!(f, "{} holds a king", SQUARE_SYM[square.to_index()]),
+            ChessError::KingGivesCheck(square) => write!(f, "king on {} gives check", SQUARE_SYM[square.to_index()]),
+            ChessError::InvalidPieceChar(c) => write!(f, "invalid piece character {:?}", c),
+            ChessError::InvalidPromotion => write!(f, "can't promote to king or pawn"),
+            ChessError::InvalidSquare => write!(f, "invalid square name"),
+            ChessError::InvalidFen(reason) => write!(f, "invalid fen: {}", reason),
+            ChessError::InvalidEncoding(reason) => write!(f, "invalid packed position: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ChessError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitboard::*;
+    use crate::chessmove::{ChessMove, MoveType};
+    use crate::*;
+
+    #[test]
+    fn try_variants_report_missing_king() {
+        let mut chessboard = ChessBoard::start_pos();
+        chessboard.piece_bbs[cpt_index!(K)] = BitBoard::ZERO;
+        chessboard.mailbox[3] = None;
+        let error = ChessError::KingNotFound(Side::White);
+        assert_eq!(chessboard.try_king_square(), Err(error));
+        assert_eq!(chessboard.try_is_king_in_check(Side::White), Err(error));
+        assert_eq!(chessboard.try_is_able_kingside_castle(Side::White), Err(error));
+        assert_eq!(chessboard.try_check_mask(), Err(error));
+        assert_eq!(chessboard.try_is_piece_pinned(Square::new(3)), Err(ChessError::EmptySquare(Square::new(3))));
+        assert_eq!(chessboard.try_is_king_in_check(Side::Black), Ok(false));
+    }
+
+    #[test]
+    fn try_variants_reject_invalid_input() {
+        assert_eq!(try_sym_index('x'), Err(ChessError::InvalidPieceChar('x')));
+        assert_eq!(try_sym_index('q'), Ok(7));
+        let promotion = MoveType::Promotion(PieceType::King);
+        assert!(ChessMove::try_new(Square::new(48), Square::new(56), promotion) == Err(ChessError::InvalidPromotion));
+        let chessboard = ChessBoard::start_pos();
+        assert_eq!(chessboard.try_is_piece_pinned(Square::new(3)), Err(ChessError::KingAsPiece(Square::new(3))));
+    }
+
+    #[test]
+    fn check_mask_with_knight_checker() {
+        // black knight on d3 checks the white king on e1
+        let mut chessboard = ChessBoard::start_pos();
+        chessboard.piece_bbs[cpt_index!(n)] |= BitBoard::nth(Square::new(20));
+        chessboard.mailbox[20] = Some(cpt!(n));
+        chessboard.check_bb = BitBoard::nth(Square::new(20));
+        assert_eq!(chessboard.try_check_mask(), Ok(BitBoard::nth(Square::new(20))));
+    }
+}
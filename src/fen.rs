@@ -0,0 +1,200 @@
+use super::*;
+
+/* ==== fen ==== */
+
+// Forsyth-Edwards Notation, e.g. the starting position is
+// rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1
+// ranks are listed from 8 to 1, files from a to h, so the first square written is a8 (index 63).
+
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+const CASTLE_CHARS: [char; 4] = ['K', 'Q', 'k', 'q'];
+
+impl ChessBoard {
+    // parses the position only, use validate() to check that it is legal
+    // the move counters are optional and default to "0 1"
+    pub fn from_fen(fen: &str) -> Result<ChessBoard, ChessError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() < 4 || fields.len() > 6 {
+            return Err(ChessError::InvalidFen("expected 4 to 6 fields"));
+        }
+
+        let mut piece_bbs: [BitBoard; 12] = [BitBoard::ZERO; 12];
+        let mut mailbox: [Option<ChessPiece>; 64] = [None; 64];
+        let ranks: Vec<&str> = fields[0].split('/').collect();
+        if ranks.len() != 8 {
+            return Err(ChessError::InvalidFen("expected 8 ranks"));
+        }
+        for (i, rank) in ranks.iter().enumerate() {
+            // the a-file square of the rank, files decrease in index towards h
+            let mut file: usize = 0;
+            for c in rank.chars() {
+                match c {
+                    '1'..='8' => file += c as usize - '0' as usize,
+                    _ => {
+                        let index = match try_sym_index(c) {
+                            Ok(x) => x,
+                            Err(_) => return Err(ChessError::InvalidFen("invalid piece character")),
+                        };
+                        if file >= 8 {
+                            return Err(ChessError::InvalidFen("rank is too long"));
+                        }
+                        let square = Square::new(((7 - i) * 8 + (7 - file)) as u8);
+                        piece_bbs[index] |= BitBoard::nth(square);
+                        mailbox[square.to_index()] = Some(CHESS_PIECES[index]);
+                        file += 1;
+                    }
+                }
+                if file > 8 {
+                    return Err(ChessError::InvalidFen("rank is too long"));
+                }
+            }
+            if file != 8 {
+                return Err(ChessError::InvalidFen("rank is too short"));
+            }
+        }
+
+        let side_to_move = match fields[1] {
+            "w" => Side::White,
+            "b" => Side::Black,
+            _ => return Err(ChessError::InvalidFen("side to move must be w or b")),
+        };
+
+        let mut castle_bools: [bool; 4] = [false; 4];
+        if fields[2] != "-" {
+            for c in fields[2].chars() {
+                match CASTLE_CHARS.iter().position(|x| *x == c) {
+                    Some(i) if castle_bools[i] == false => castle_bools[i] = true,
+                    _ => return Err(ChessError::InvalidFen("invalid castling rights")),
+                }
+            }
+        }
+
+        let enpassant_bb = match fields[3] {
+            "-" => BitBoard::ZERO,
+            name => match name.parse::<Square>() {
+                Ok(square) => BitBoard::nth(square),
+                Err(_) => return Err(ChessError::InvalidFen("invalid en passant square")),
+            },
+        };
+
+        let half_move_clock: u16 = match fields.get(4) {
+            Some(x) => x.parse().map_err(|_| ChessError::InvalidFen("invalid half move clock"))?,
+            None => 0,
+        };
+        let full_move_number: u16 = match fields.get(5) {
+            Some(x) => x.parse().map_err(|_| ChessError::InvalidFen("invalid full move number"))?,
+            None => 1,
+        };
+
+        let mut chessboard = ChessBoard {
+            piece_bbs,
+            mailbox,
+            castle_bools,
+            enpassant_bb,
+            check_bb: BitBoard::ZERO,
+            side_to_move,
+            half_move_clock,
+            full_move_number,
+        };
+        // check_bb is derived, a missing king is left for validate() to report
+        chessboard.check_bb = chessboard.compute_check_bb();
+        Ok(chessboard)
+    }
+
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+        for i in (0..8).rev() {
+            let mut empty: u32 = 0;
+            for file in 0..8 {
+                match self.mailbox[i * 8 + (7 - file)] {
+                    Some(piece) => {
+                        if empty > 0 {
+                            fen.push(char::from_digit(empty, 10).unwrap());
+                            empty = 0;
+                        }
+                        fen.push(ASCII_SYM[cp_index(piece)]);
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                fen.push(char::from_digit(empty, 10).unwrap());
+            }
+            if i > 0 {
+                fen.push('/');
+            }
+        }
+
+        fen.push_str(match self.side_to_move {
+            Side::White => " w ",
+            Side::Black => " b ",
+        });
+
+        if self.castle_bools.contains(&true) {
+            for (c, can_castle) in CASTLE_CHARS.iter().zip(self.castle_bools) {
+                if can_castle {
+                    fen.push(*c);
+                }
+            }
+        } else {
+            fen.push('-');
+        }
+
+        match self.enpassant_bb.lsb_square() {
+            Some(square) => fen.push_str(&format!(" {}", square)),
+            None => fen.push_str(" -"),
+        }
+        fen.push_str(&format!(" {} {}", self.half_move_clock, self.full_move_number));
+        fen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::square::sq;
+
+    #[test]
+    fn start_pos_round_trip() {
+        assert_eq!(ChessBoard::start_pos().to_fen(), START_FEN);
+        assert_eq!(ChessBoard::from_fen(START_FEN), Ok(ChessBoard::start_pos()));
+    }
+
+    #[test]
+    fn round_trip() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K2R b K - 12 40",
+        ] {
+            let chessboard = ChessBoard::from_fen(fen).unwrap();
+            assert_eq!(chessboard.validate(), Ok(()), "{}", fen);
+            assert_eq!(chessboard.to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn derives_checkers() {
+        let chessboard = ChessBoard::from_fen("4k3/8/8/8/8/8/4r3/4K3 w - - 0 1").unwrap();
+        assert_eq!(chessboard.check_bb, BitBoard::nth(sq("e2")));
+    }
+
+    #[test]
+    fn rejects_malformed_fen() {
+        for fen in [
+            "",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNRR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkqK - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e9 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1",
+        ] {
+            assert!(ChessBoard::from_fen(fen).is_err(), "{}", fen);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use crate::bitboard::{Side, read_u64};
+use crate::square::Square;
+
+/* ==== king and pawn versus king ==== */
+
+// a win/draw bitbase for KPK, generated by build.rs by retrograde iteration (index layout in
+// data/kpk.rs). positions are seen from the strong side, which is white (the pawn moves towards
+// rank 8). for a black pawn, mirror the ranks of all three squares (square ^ 56) and the side to move.
+// tablebase::probe_wdl does this for a whole board.
+
+include!("data/kpk.rs");
+
+static KPK_BITBASE: [u64; KPK_SIZE / 64] = bitbase(include_bytes!(concat!(env!("OUT_DIR"), "/kpk.bin")));
+
+// build.rs writes the bitbase as little endian u64s, like the attack tables
+const fn bitbase(bytes: &[u8]) -> [u64; KPK_SIZE / 64] {
+    assert!(bytes.len() == KPK_SIZE / 8, "bitbase error: generated bitbase has the wrong size!");
+    let mut bits: [u64; KPK_SIZE / 64] = [0; KPK_SIZE / 64];
+    let mut i: usize = 0;
+    while i < KPK_SIZE / 64 {
+        bits[i] = read_u64(bytes, i);
+        i += 1;
+    }
+    return bits;
+}
+
+// Some(true) if the strong side (white, pawn moving up the board) wins with best play
+// None for a pawn on rank 1 or 8, which is not a KPK position. other illegal positions are
+// reported as not winning
+pub const fn probe(strong_king: Square, pawn: Square, weak_king: Square, side_to_move: Side) -> Option<bool> {
+    let (mut strong_king, mut pawn, mut weak_king) = (strong_king.to_index(), pawn.to_index(), weak_king.to_index());
+    if pawn < 8 || pawn >= 56 {
+        return None;
+    }
+    // mirror e-h file pawns onto the a-d files
+    if pawn % 8 < 4 {
+        strong_king ^= 7;
+        pawn ^= 7;
+        weak_king ^= 7;
+    }
+    let i = index(matches!(side_to_move, Side::White), strong_king, weak_king, pawn);
+    Some((KPK_BITBASE[i / 64] >> (i % 64)) & 1 == 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn win(strong_king: &str, pawn: &str, weak_king: &str, side_to_move: Side) -> bool {
+        probe(strong_king.parse().unwrap(), pawn.parse().unwrap(), weak_king.parse().unwrap(), side_to_move).unwrap()
+    }
+
+    #[test]
+    fn known_positions() {
+        // king on the sixth rank in front of its pawn wins, whoever is to move
+        assert!(win("e6", "e5", "e8", Side::White));
+        assert!(win("e6", "e5", "e8", Side::Black));
+        assert!(win("d6", "d5", "d8", Side::Black));
+        // the rook pawn is a draw with the defending king in the corner
+        assert!(win("b6", "a5", "a8", Side::White) == false);
+        assert!(win("g6", "h5", "h8", Side::Black) == false);
+        // opposition: the side to move decides
+        assert!(win("e5", "e4", "e7", Side::White) == false);
+        assert!(win("e5", "e4", "e7", Side::Black));
+        // rule of the square
+        assert!(win("h1", "c5", "h8", Side::White));
+        assert!(win("h1", "c5", "f8", Side::White) == false);
+        // the weak king takes the undefended pawn
+        assert!(win("a1", "e4", "d4", Side::Black) == false);
+        // adjacent kings are illegal
+        assert!(win("e4", "c2", "e5", Side::White) == false);
+        // a pawn on the back rank is not a KPK position
+        for pawn in ["c1", "c8"] {
+            assert_eq!(probe("e4".parse().unwrap(), pawn.parse().unwrap(), "e6".parse().unwrap(), Side::White), None);
+        }
+    }
+}
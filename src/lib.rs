@@ -1,8 +1,34 @@
 mod bitboard;
 mod chessmove;
+mod error;
+mod fen;
+pub mod kpk;
+pub mod magic;
 mod movegen;
+pub mod nnue;
+pub mod ordering;
+pub mod packed;
+#[cfg(feature = "bmi2")]
+pub mod pext;
+#[cfg(feature = "serde")]
+mod serialize;
+pub mod setwise;
 mod square;
-use crate::{bitboard::*, square::Square};
+pub mod tablebase;
+pub mod timeman;
+mod validate;
+pub use crate::{
+    bitboard::{
+        BitBoard, PieceType, Side, SquareIter, get_bishop_attack, get_b_pawn_attack, get_king_attack, get_knight_attack,
+        get_pawn_attack, get_queen_attack, get_rook_attack, get_w_pawn_attack, magic_bishop_attack, magic_rook_attack,
+    },
+    chessmove::{ChessMove, MoveType},
+    error::ChessError,
+    fen::START_FEN,
+    square::Square,
+    validate::PositionError,
+};
+use crate::bitboard::*;
 
 /* chessboard specific bitboard functions and definitions*/
 
@@ -16,6 +42,7 @@ pub struct ChessBoard {
     check_bb: BitBoard, //piece locations causing the check
     side_to_move: Side,
     half_move_clock: u16,
+    full_move_number: u16,
 }
 
 impl Default for ChessBoard {
@@ -62,6 +89,7 @@ impl ChessBoard {
             side_to_move: Side::White,
             check_bb: BitBoard::ZERO,
             half_move_clock: 0,
+            full_move_number: 1,
         }
     }
     pub const fn duplicate(&self) -> ChessBoard {
@@ -72,6 +100,7 @@ impl ChessBoard {
             enpassant_bb: self.enpassant_bb,
             side_to_move: self.side_to_move,
             half_move_clock: self.half_move_clock,
+            full_move_number: self.full_move_number,
             check_bb: self.check_bb,
         }
     }
@@ -106,28 +135,49 @@ impl ChessBoard {
         return bitboard;
     }
     //TODO maybe is_square_attacked should have parameterized blockers?
-    pub const fn is_square_attacked(&self, square: Square, attacker_side: Side) -> bool {
-        let blockers = self.blockers();
-        match attacker_side {
-            Side::White => {
-                return (get_b_pawn_attack(square).bit_and(&self.piece_bbs[5])).is_not_zero()
-                    || (get_rook_attack(square, blockers).bit_and(&self.piece_bbs[4])).is_not_zero()
-                    || (get_bishop_attack(square, blockers).bit_and(&self.piece_bbs[3])).is_not_zero()
-                    || (get_knight_attack(square).bit_and(&self.piece_bbs[2])).is_not_zero()
-                    || (get_queen_attack(square, blockers).bit_and(&self.piece_bbs[1])).is_not_zero()
-                    || (get_king_attack(square).bit_and(&self.piece_bbs[0])).is_not_zero();
-            }
-            Side::Black => {
-                return (get_b_pawn_attack(square).bit_and(&self.piece_bbs[11])).is_not_zero()
-                    || (get_rook_attack(square, blockers).bit_and(&self.piece_bbs[10])).is_not_zero()
-                    || (get_bishop_attack(square, blockers).bit_and(&self.piece_bbs[9])).is_not_zero()
-                    || (get_knight_attack(square).bit_and(&self.piece_bbs[8])).is_not_zero()
-                    || (get_queen_attack(square, blockers).bit_and(&self.piece_bbs[7])).is_not_zero()
-                    || (get_king_attack(square).bit_and(&self.piece_bbs[6])).is_not_zero();
+    bmi2_const_fn! {
+        pub fn is_square_attacked(&self, square: Square, attacker_side: Side) -> bool {
+            let blockers = self.blockers();
+            match attacker_side {
+                Side::White => {
+                    return (get_b_pawn_attack(square).bit_and(&self.piece_bbs[5])).is_not_zero()
+                        || (get_rook_attack(square, blockers).bit_and(&self.piece_bbs[4])).is_not_zero()
+                        || (get_bishop_attack(square, blockers).bit_and(&self.piece_bbs[3])).is_not_zero()
+                        || (get_knight_attack(square).bit_and(&self.piece_bbs[2])).is_not_zero()
+                        || (get_queen_attack(square, blockers).bit_and(&self.piece_bbs[1])).is_not_zero()
+                        || (get_king_attack(square).bit_and(&self.piece_bbs[0])).is_not_zero();
+                }
+                Side::Black => {
+                    return (get_w_pawn_attack(square).bit_and(&self.piece_bbs[11])).is_not_zero()
+                        || (get_rook_attack(square, blockers).bit_and(&self.piece_bbs[10])).is_not_zero()
+                        || (get_bishop_attack(square, blockers).bit_and(&self.piece_bbs[9])).is_not_zero()
+                        || (get_knight_attack(square).bit_and(&self.piece_bbs[8])).is_not_zero()
+                        || (get_queen_attack(square, blockers).bit_and(&self.piece_bbs[7])).is_not_zero()
+                        || (get_king_attack(square).bit_and(&self.piece_bbs[6])).is_not_zero();
+                }
             }
         }
     }
 
+    // bitboard of attacker_side's pieces attacking square
+    bmi2_const_fn! {
+        pub fn attackers(&self, square: Square, attacker_side: Side) -> BitBoard {
+            let blockers = self.blockers();
+            let (pawn_attack, offset) = match attacker_side {
+                Side::White => (get_b_pawn_attack(square), 0),
+                Side::Black => (get_w_pawn_attack(square), 6),
+            };
+            let diagonals = self.piece_bbs[offset + 1].bit_or(&self.piece_bbs[offset + 3]);
+            let laterals = self.piece_bbs[offset + 1].bit_or(&self.piece_bbs[offset + 4]);
+            pawn_attack
+                .bit_and(&self.piece_bbs[offset + 5])
+                .bit_or(&get_knight_attack(square).bit_and(&self.piece_bbs[offset + 2]))
+                .bit_or(&get_bishop_attack(square, blockers).bit_and(&diagonals))
+                .bit_or(&get_rook_attack(square, blockers).bit_and(&laterals))
+                .bit_or(&get_king_attack(square).bit_and(&self.piece_bbs[offset]))
+        }
+    }
+
     //pub(crate) const fn is_square_behind_king_attacked(&self, square: Square, side: Side) -> bool {
     //    let blockers = self.blockers().pop_bit(self.king_square());
     //    match side {//FIXME maybe can do bit_and, so only one is_not_zero() call?
@@ -150,284 +200,258 @@ impl ChessBoard {
     //    }
     //}
 
-    pub(crate) const fn is_square_attacked_removed_piece(&self, square: Square, side: Side, removed_square: Square) -> bool {
-        let blockers = self.blockers().pop_bit(removed_square);
-        match side {//FIXME maybe can do bit_and, so only one is_not_zero() call?
-            Side::White => {
-                return (get_b_pawn_attack(square).bit_and(&self.piece_bbs[11])).is_not_zero()
-                    || (get_rook_attack(square, blockers).bit_and(&self.piece_bbs[10])).is_not_zero()
-                    || (get_bishop_attack(square, blockers).bit_and(&self.piece_bbs[9])).is_not_zero()
-                    || (get_knight_attack(square).bit_and(&self.piece_bbs[8])).is_not_zero()
-                    || (get_queen_attack(square, blockers).bit_and(&self.piece_bbs[7])).is_not_zero()
-                    || (get_king_attack(square).bit_and(&self.piece_bbs[6])).is_not_zero();
+    bmi2_const_fn! {
+        pub(crate) fn is_square_attacked_removed_piece(&self, square: Square, side: Side, removed_square: Square) -> bool {
+            let blockers = self.blockers().pop_bit(removed_square);
+            match side {//FIXME maybe can do bit_and, so only one is_not_zero() call?
+                Side::White => {
+                    return (get_w_pawn_attack(square).bit_and(&self.piece_bbs[11])).is_not_zero()
+                        || (get_rook_attack(square, blockers).bit_and(&self.piece_bbs[10])).is_not_zero()
+                        || (get_bishop_attack(square, blockers).bit_and(&self.piece_bbs[9])).is_not_zero()
+                        || (get_knight_attack(square).bit_and(&self.piece_bbs[8])).is_not_zero()
+                        || (get_queen_attack(square, blockers).bit_and(&self.piece_bbs[7])).is_not_zero()
+                        || (get_king_attack(square).bit_and(&self.piece_bbs[6])).is_not_zero();
+                }
+                Side::Black => {
+                    return (get_b_pawn_attack(square).bit_and(&self.piece_bbs[5])).is_not_zero()
+                        || (get_rook_attack(square, blockers).bit_and(&self.piece_bbs[4])).is_not_zero()
+                        || (get_bishop_attack(square, blockers).bit_and(&self.piece_bbs[3])).is_not_zero()
+                        || (get_knight_attack(square).bit_and(&self.piece_bbs[2])).is_not_zero()
+                        || (get_queen_attack(square, blockers).bit_and(&self.piece_bbs[1])).is_not_zero()
+                        || (get_king_attack(square).bit_and(&self.piece_bbs[0])).is_not_zero();
+                }
             }
-            Side::Black => {
-                return (get_b_pawn_attack(square).bit_and(&self.piece_bbs[5])).is_not_zero()
-                    || (get_rook_attack(square, blockers).bit_and(&self.piece_bbs[4])).is_not_zero()
-                    || (get_bishop_attack(square, blockers).bit_and(&self.piece_bbs[3])).is_not_zero()
-                    || (get_knight_attack(square).bit_and(&self.piece_bbs[2])).is_not_zero()
-                    || (get_queen_attack(square, blockers).bit_and(&self.piece_bbs[1])).is_not_zero()
-                    || (get_king_attack(square).bit_and(&self.piece_bbs[0])).is_not_zero();
+        }
+    }
+
+    bmi2_const_fn! {
+        pub fn is_king_in_check(&self, king_side: Side) -> bool {
+            match self.try_is_king_in_check(king_side) {
+                Ok(x) => x,
+                Err(_) => panic!("king_is_in_check error: king not found!"),
             }
         }
     }
 
-    pub const fn is_king_in_check(&self, king_side: Side) -> bool {
-        let i = match king_side {
-            Side::White => 0,
-            Side::Black => 6,
-        };
+    bmi2_const_fn! {
+        pub fn try_is_king_in_check(&self, king_side: Side) -> Result<bool, ChessError> {
+            let i = match king_side {
+                Side::White => 0,
+                Side::Black => 6,
+            };
 
-        let square = match self.piece_bbs[i].lsb_square() {
-            Some(x) => x,
-            None => panic!("king_is_in_check error: king not found!"),
-        };
+            let square = match self.piece_bbs[i].lsb_square() {
+                Some(x) => x,
+                None => return Err(ChessError::KingNotFound(king_side)),
+            };
 
-        self.is_square_attacked(square, self.side_to_move.update())
+            Ok(self.is_square_attacked(square, king_side.update()))
+        }
     }
 
     // castling kingside
     pub(crate) fn is_able_kingside_castle(&self, side: Side) -> bool {
-        let king_square: Square;
-        let castling_mask: BitBoard;
-        let castling_index: usize;
-        let blockers = self.blockers();
-
-        match side {
-            Side::White => {
-                king_square = match self.piece_bbs[cpt_index!(K)].lsb_square() {
-                    Some(square) => square,
-                    None => panic!("generate moves: white king not found"),
-                };
-                castling_mask= W_KING_SIDE_CASTLE_MASK;
-                castling_index = 0;
-                //queen_castling_mask = W_QUEEN_SIDE_CASTLE_MASK;
-                //queen_castling_index = 1;
-                //friends = w_blockers;
-                //enemies = b_blockers;
-            }
-            Side::Black => {
-                king_square = match self.piece_bbs[cpt_index!(k)].lsb_square() {
-                    Some(square) => square,
-                    None => panic!("generate moves: black king not found"),
-            };
-            castling_mask= B_KING_SIDE_CASTLE_MASK;
-            castling_index = 2;
-            //queen_castling_mask = B_QUEEN_SIDE_CASTLE_MASK;
-            //queen_castling_index = 3;
-            //friends = b_blockers;
-            //enemies = w_blockers;
-            }
-        }
-        // check if friendly side can still castle, and if there are blockers in relevant squares
-        if (self.castle_bools[castling_index] == false) ||  (blockers.bit_and(&castling_mask).is_zero() == false) {
-            return false;
-        }
-        let mut squares = castling_mask;
-        while squares.is_not_zero() {
-            let square = squares.lsb_square().unwrap();
-            if self.is_square_attacked(square, side.update()) {
-                return false;
-            }
-            squares = squares.pop_bit(square);
-        }
-        return true;
+        self.try_is_able_kingside_castle(side).expect("generate moves: king not found")
+    }
+
+    pub fn try_is_able_kingside_castle(&self, side: Side) -> Result<bool, ChessError> {
+        let (castling_mask, castling_index) = match side {
+            Side::White => (W_KING_SIDE_CASTLE_MASK, 0),
+            Side::Black => (B_KING_SIDE_CASTLE_MASK, 2),
+        };
+        // the king passes through and lands on the squares between it and the rook
+        self.try_is_able_castle(side, castling_mask, castling_index, castling_mask)
     }
     // castling queenside
     pub(crate) fn is_able_queenside_castle(&self, side: Side) -> bool {
-        let king_square: Square;
-        let castling_mask: BitBoard;
-        let castling_index: usize;
-        let blockers = self.blockers();
-
-        match side {
-            Side::White => {
-                king_square = match self.piece_bbs[cpt_index!(K)].lsb_square() {
-                    Some(square) => square,
-                    None => panic!("generate moves: white king not found"),
-                };
-                //king_castling_mask= W_KING_SIDE_CASTLE_MASK;
-                //king_castling_index = 0;
-                castling_mask = W_QUEEN_SIDE_CASTLE_MASK;
-                castling_index = 1;
-                //friends = w_blockers;
-                //enemies = b_blockers;
-            }
-            Side::Black => {
-                king_square = match self.piece_bbs[cpt_index!(k)].lsb_square() {
-                    Some(square) => square,
-                    None => panic!("generate moves: black king not found"),
-                };
-                //king_castling_mask= B_KING_SIDE_CASTLE_MASK;
-                //king_castling_index = 2;
-                castling_mask = B_QUEEN_SIDE_CASTLE_MASK;
-                castling_index = 3;
-                //friends = b_blockers;
-                //enemies = w_blockers;
-            }
-        }
+        self.try_is_able_queenside_castle(side).expect("generate moves: king not found")
+    }
+
+    pub fn try_is_able_queenside_castle(&self, side: Side) -> Result<bool, ChessError> {
+        let (castling_mask, castling_index) = match side {
+            Side::White => (W_QUEEN_SIDE_CASTLE_MASK, 1),
+            Side::Black => (B_QUEEN_SIDE_CASTLE_MASK, 3),
+        };
+        // the b-file square must be empty, but the king never crosses it so it may be attacked
+        let king_path = castling_mask.bit_and(&COL_MASKS[6].bit_not());
+        self.try_is_able_castle(side, castling_mask, castling_index, king_path)
+    }
 
+    fn try_is_able_castle(
+        &self,
+        side: Side,
+        castling_mask: BitBoard,
+        castling_index: usize,
+        king_path: BitBoard,
+    ) -> Result<bool, ChessError> {
+        let king_square = match side {
+            Side::White => self.piece_bbs[cpt_index!(K)].lsb_square(),
+            Side::Black => self.piece_bbs[cpt_index!(k)].lsb_square(),
+        };
+        let king_square = match king_square {
+            Some(square) => square,
+            None => return Err(ChessError::KingNotFound(side)),
+        };
         // check if friendly side can still castle, and if there are blockers in relevant squares
-        if (self.castle_bools[castling_index] == false) ||  (blockers.bit_and(&castling_mask).is_zero() == false) {
-            return false;
+        if (self.castle_bools[castling_index] == false) || (self.blockers().bit_and(&castling_mask).is_zero() == false) {
+            return Ok(false);
         }
-        let mut squares = castling_mask;
-        while squares.is_not_zero() {
-            let square = squares.lsb_square().unwrap();
+        // can't castle out of, or through check
+        if self.is_square_attacked(king_square, side.update()) {
+            return Ok(false);
+        }
+        for square in king_path {
             if self.is_square_attacked(square, side.update()) {
-                return false;
+                return Ok(false);
             }
-            squares = squares.pop_bit(square);
         }
-        return true;
-    }
-
-    pub(crate) const fn is_piece_pinned(&self, square: Square) -> bool {
-        //FIXME ?? duplicating self, mutating it, is it necessary?
-        let mut chessboard: ChessBoard = self.duplicate();
-        let piece = self.mailbox[square.to_index()].expect("is_piece_pinned error: mailbox square is empty!");
-        let mut piece_bb = self.piece_bbs[cp_index(piece)].bit_and(&BitBoard::nth(square).bit_not());
-        //let mut mailbox = self.mailbox;
-        chessboard.mailbox[cp_index(piece)] = None;
-        let side = self.side_to_move;
-        assert!(matches!(piece, cpt!(K) | cpt!(k)) == false, "is_piece_pinned error: checking if king is pinned");
-        if self.is_king_in_check(side) == false {
-            // if king is not in check, test if removing piece causes king to be in check
-            return chessboard.is_king_in_check(side); //FIXME
-        } else {
-            //check if king are present for both sides
-            assert!(self.piece_bbs[0].count_ones() == 1 && self.piece_bbs[6].count_ones() == 1);
-            let king_square: Square;
-            let diagonals: BitBoard;
-            let laterals: BitBoard;
-            let enemies: BitBoard;
-            match side {
-                Side::White => {
-                    king_square = self.piece_bbs[cpt_index!(K)].lsb_square().unwrap();
-                    diagonals = self.piece_bbs[cpt_index!(q)].bit_or(&self.piece_bbs[cpt_index!(b)]);
-                    laterals = self.piece_bbs[cpt_index!(q)].bit_or(&self.piece_bbs[cpt_index!(r)]);
-                    enemies = self.black_blockers();
-                }
-                Side::Black => {
-                    king_square = self.piece_bbs[cpt_index!(k)].lsb_square().unwrap();
-                    diagonals = self.piece_bbs[cpt_index!(Q)].bit_or(&self.piece_bbs[cpt_index!(B)]);
-                    laterals = self.piece_bbs[cpt_index!(Q)].bit_or(&self.piece_bbs[cpt_index!(R)]);
-                    enemies = self.white_blockers();
-                }
+        return Ok(true);
+    }
+
+    bmi2_const_fn! {
+        pub(crate) fn is_piece_pinned(&self, square: Square) -> bool {
+            match self.try_is_piece_pinned(square) {
+                Ok(x) => x,
+                Err(ChessError::EmptySquare(_)) => panic!("is_piece_pinned error: mailbox square is empty!"),
+                Err(ChessError::KingAsPiece(_)) => panic!("is_piece_pinned error: checking if king is pinned"),
+                Err(_) => panic!("is_piece_pinned error: king not found!"),
             }
-            // blockers without friendly king
-            let removed_blockers = self.blockers().bit_and(&BitBoard::nth(square).bit_not());
-            // position of diagonal attackers
-            let diag_attackers = get_bishop_attack(square, removed_blockers).bit_and(&diagonals);
-            // position of lateral attackers
-            let latr_attackers = get_rook_attack(square, removed_blockers).bit_and(&laterals);
-
-            // if king is in check, see if square is in ray of a checking piece
-            // note: why are we testing for all rays from the king here?
-            let mut potential_pinners = enemies.bit_and(&diag_attackers.bit_or(&latr_attackers));
-            while potential_pinners.is_not_zero() {
-                let potential_pinner = potential_pinners.lsb_square().unwrap();
-                // check if piece is between king and potential_pinner
-                if RAYS[king_square.to_index()][potential_pinner.to_index()].nth_is_not_zero(square) {
-                    return true;
-                }
-                potential_pinners = potential_pinners.pop_bit(potential_pinner);
+        }
+    }
+
+    bmi2_const_fn! {
+        pub fn try_is_piece_pinned(&self, square: Square) -> Result<bool, ChessError> {
+            match self.mailbox[square.to_index()] {
+                None => return Err(ChessError::EmptySquare(square)),
+                Some(cpt!(K) | cpt!(k)) => return Err(ChessError::KingAsPiece(square)),
+                Some(_) => {}
+            }
+            match self.try_calculate_pin_data(square) {
+                Ok((pinners, _)) => Ok(pinners.is_not_zero()),
+                Err(e) => Err(e),
             }
         }
-        return false;
-    }
-
-    pub(crate) const fn calculate_pin_data(&self, square: Square) -> (BitBoard,BitBoard) {
-        //FIXME ?? duplicating self, mutating it, is it necessary?
-        //let is_pinned: bool;
-        let mut pinners: BitBoard = BitBoard::ZERO;
-        let mut pin_mask: BitBoard = BitBoard::ZERO;
-        //let mut chessboard: ChessBoard = self.duplicate();
-        let piece = self.mailbox[square.to_index()].expect("is_piece_pinned error: mailbox square is empty!");
-        //let mut piece_bb = self.piece_bbs[cp_index(piece)].bit_and(&BitBoard::nth(square).bit_not());
-        let mut mailbox = self.mailbox;
-        mailbox[cp_index(piece)] = None;
-        let side = self.side_to_move;
-        //assert!(matches!(piece, cpt!(K) | cpt!(k)) == false, "is_piece_pinned error: checking if king is pinned");
-        if matches!(piece, cpt!(K) | cpt!(k)) {
-            return (BitBoard::ZERO, BitBoard::ZERO);
+    }
+
+    bmi2_const_fn! {
+        pub(crate) fn calculate_pin_data(&self, square: Square) -> (BitBoard,BitBoard) {
+            match self.try_calculate_pin_data(square) {
+                Ok(x) => x,
+                Err(ChessError::EmptySquare(_)) => panic!("is_piece_pinned error: mailbox square is empty!"),
+                Err(_) => panic!("calculate_pin_data error: king not found!"),
+            }
         }
-        if self.is_king_in_check(side) == false {
-            // if king is not in check, test if removing piece causes king to be in check
-            //is_pinned = chessboard.is_king_in_check(side); //FIXME
-        } else {
-            //check if king are present for both sides
-            assert!(self.piece_bbs[cpt_index!(K)].count_ones() == 1 && self.piece_bbs[cpt_index!(k)].count_ones() == 1);
-            let king_square: Square;
-            let diagonals: BitBoard;
-            let laterals: BitBoard;
-            let enemies: BitBoard;
-            match side {
-                Side::White => {
-                    king_square = self.piece_bbs[cpt_index!(K)].lsb_square().unwrap();
-                    diagonals = self.piece_bbs[cpt_index!(q)].bit_or(&self.piece_bbs[cpt_index!(b)]);
-                    laterals = self.piece_bbs[cpt_index!(q)].bit_or(&self.piece_bbs[cpt_index!(r)]);
-                    enemies = self.black_blockers();
-                }
-                Side::Black => {
-                    king_square = self.piece_bbs[cpt_index!(k)].lsb_square().unwrap();
-                    diagonals = self.piece_bbs[cpt_index!(Q)].bit_or(&self.piece_bbs[cpt_index!(B)]);
-                    laterals = self.piece_bbs[cpt_index!(Q)].bit_or(&self.piece_bbs[cpt_index!(R)]);
-                    enemies = self.white_blockers();
-                }
+    }
+
+    // pieces pinning the piece on square to its own king, and the squares it can still move to
+    // (between its king and the pinners, the pinners included). kings are never pinned.
+    bmi2_const_fn! {
+        pub fn try_calculate_pin_data(&self, square: Square) -> Result<(BitBoard, BitBoard), ChessError> {
+            let piece = match self.mailbox[square.to_index()] {
+                Some(x) => x,
+                None => return Err(ChessError::EmptySquare(square)),
+            };
+            if matches!(piece, cpt!(K) | cpt!(k)) {
+                return Ok((BitBoard::ZERO, BitBoard::ZERO));
             }
-            // blockers without friendly king
+            let (king_bb, diagonals, laterals) = match piece.0 {
+                Side::White => (
+                    self.piece_bbs[cpt_index!(K)],
+                    self.piece_bbs[cpt_index!(q)].bit_or(&self.piece_bbs[cpt_index!(b)]),
+                    self.piece_bbs[cpt_index!(q)].bit_or(&self.piece_bbs[cpt_index!(r)]),
+                ),
+                Side::Black => (
+                    self.piece_bbs[cpt_index!(k)],
+                    self.piece_bbs[cpt_index!(Q)].bit_or(&self.piece_bbs[cpt_index!(B)]),
+                    self.piece_bbs[cpt_index!(Q)].bit_or(&self.piece_bbs[cpt_index!(R)]),
+                ),
+            };
+            let king_square = match king_bb.lsb_square() {
+                Some(x) => x,
+                None => return Err(ChessError::KingNotFound(piece.0)),
+            };
+            // enemy sliders that would see the king with the piece lifted off the board
             let removed_blockers = self.blockers().bit_and(&BitBoard::nth(square).bit_not());
-            // position of diagonal attackers
-            let diag_attackers = get_bishop_attack(square, removed_blockers).bit_and(&diagonals);
-            // position of lateral attackers
-            let latr_attackers = get_rook_attack(square, removed_blockers).bit_and(&laterals);
-
-            // if king is in check, see if square is in ray of a checking piece
-            // note: why are we testing for all rays from the king here?
-            let mut potential_pinners = enemies.bit_and(&diag_attackers.bit_or(&latr_attackers));
-            while potential_pinners.is_not_zero() {
-                let potential_pinner = potential_pinners.lsb_square().unwrap();
-                // check if piece is between king and potential_pinner
-                if RAYS[king_square.to_index()][potential_pinner.to_index()].nth_is_not_zero(square) {
-                    //is_pinned = true;
+            let diag_attackers = get_bishop_attack(king_square, removed_blockers).bit_and(&diagonals);
+            let latr_attackers = get_rook_attack(king_square, removed_blockers).bit_and(&laterals);
+            let mut potential_pinners = diag_attackers.bit_or(&latr_attackers);
+
+            let mut pinners: BitBoard = BitBoard::ZERO;
+            let mut pin_mask: BitBoard = BitBoard::ZERO;
+            while let Some(potential_pinner) = potential_pinners.lsb_square() {
+                // only those with the piece in between pin it, the others give check
+                let ray = RAYS[king_square.to_index()][potential_pinner.to_index()];
+                if ray.nth_is_not_zero(square) {
                     pinners = pinners.bit_or(&BitBoard::nth(potential_pinner));
-                    pin_mask = pin_mask.bit_or(&RAYS[king_square.to_index()][potential_pinner.to_index()].bit_or(&BitBoard::nth(potential_pinner)))
+                    pin_mask = pin_mask.bit_or(&ray.bit_or(&BitBoard::nth(potential_pinner)));
                 }
                 potential_pinners = potential_pinners.pop_bit(potential_pinner);
             }
+            return Ok((pinners, pin_mask));
         }
-        //is_pinned = false;
-        return (pinners, pin_mask);
     }
 
     // calculates all the squares attacked by enemy's checking pieces
     pub(crate) fn check_mask(&self) -> BitBoard {
+        match self.try_check_mask() {
+            Ok(x) => x,
+            Err(ChessError::EmptySquare(_)) => panic!("generate_moves: checker mailbox is empty"),
+            Err(ChessError::KingGivesCheck(_)) => panic!("generate_moves: king is in check by another king!"),
+            Err(_) => panic!("king_square: king must be present"),
+        }
+    }
+
+    pub fn try_check_mask(&self) -> Result<BitBoard, ChessError> {
         let mut check_bb: BitBoard = self.check_bb;
         let mut check_mask = check_bb;
+        let king_square = self.try_king_square()?;
         while check_bb.is_not_zero() {
             let checker_square = check_bb.lsb_square().unwrap();
-            match self.mailbox[checker_square.to_index()].expect("generate_moves: checker mailbox is empty") {
-                cpt!(K) | cpt!(k) => panic!("generate_moves: king is in check by another king!"),
-                cpt!(N) | cpt!(n) => continue,
-                _ => {
-                    check_mask = check_mask.bit_or(&RAYS[checker_square.to_index()][self.king_square().to_index()]);
+            match self.mailbox[checker_square.to_index()] {
+                None => return Err(ChessError::EmptySquare(checker_square)),
+                Some(cpt!(K) | cpt!(k)) => return Err(ChessError::KingGivesCheck(checker_square)),
+                // knight checks can't be blocked
+                Some(cpt!(N) | cpt!(n)) => {}
+                Some(_) => {
+                    check_mask = check_mask.bit_or(&RAYS[checker_square.to_index()][king_square.to_index()]);
                 }
             }
             check_bb = check_bb.pop_bit(checker_square);
         }
-        return check_mask;
+        return Ok(check_mask);
     }
 
     // calculates number of enemy checking piece
+    #[allow(dead_code)]
     pub(crate) fn count_checking_pieces(&self) -> u32 {
         self.check_bb.count_ones()
     }
 
+    // recomputes check_bb from the piece placement, a missing king leaves it empty
+    bmi2_const_fn! {
+        pub(crate) fn compute_check_bb(&self) -> BitBoard {
+            match self.try_king_square() {
+                Ok(king) => self.attackers(king, self.side_to_move.update()),
+                Err(_) => BitBoard::ZERO,
+            }
+        }
+    }
+
     pub(crate) const fn king_square(&self) -> Square {
-        match self.side_to_move {
-            Side::White => self.piece_bbs[cpt_index!(K)].lsb_square().expect("king_square: king must be present"),
-            Side::Black => self.piece_bbs[cpt_index!(k)].lsb_square().expect("king_square: king must be present"),
+        match self.try_king_square() {
+            Ok(x) => x,
+            Err(_) => panic!("king_square: king must be present"),
+        }
+    }
+
+    pub const fn try_king_square(&self) -> Result<Square, ChessError> {
+        let king = match self.side_to_move {
+            Side::White => self.piece_bbs[cpt_index!(K)].lsb_square(),
+            Side::Black => self.piece_bbs[cpt_index!(k)].lsb_square(),
+        };
+        match king {
+            Some(x) => Ok(x),
+            None => Err(ChessError::KingNotFound(self.side_to_move)),
         }
     }
 
@@ -447,6 +471,33 @@ impl ChessBoard {
             (Side::Black, PieceType::Pawn  ) => self.piece_bbs[11],
         }
     }
+
+    // the board as text with rank and file labels, white at the bottom unless flipped
+    pub fn draw(&self, unicode: bool, flipped: bool) -> String {
+        let symbols = if unicode { UNICODE_SYM } else { ASCII_SYM };
+        // row 0 is rank 1 and col 0 is the h-file, so both count down unless flipped
+        let order = |i: usize| if flipped { i } else { 7 - i };
+        let mut s = String::new();
+        for i in 0..8 {
+            let row = order(i);
+            s.push(RANK_CHAR[row * 8]);
+            for j in 0..8 {
+                s.push(' ');
+                match self.mailbox[row * 8 + order(j)] {
+                    Some(piece) => s.push(symbols[cp_index(piece)]),
+                    None => s.push('.'),
+                }
+            }
+            s.push('\n');
+        }
+        s.push(' ');
+        for j in 0..8 {
+            s.push(' ');
+            s.push(FILE_CHAR[order(j)]);
+        }
+        s.push('\n');
+        return s;
+    }
 }
 
 
@@ -490,3 +541,109 @@ static inline void generate_moves()
     }
 }
 */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::square::sq;
+
+    fn board(fen: &str) -> ChessBoard {
+        ChessBoard::from_fen(fen).unwrap()
+    }
+
+    fn bb(squares: &[&str]) -> BitBoard {
+        squares.iter().fold(BitBoard::ZERO, |bb, x| bb | BitBoard::nth(sq(x)))
+    }
+
+    #[test]
+    fn pinned_pieces() {
+        let chessboard = board("4r2k/8/8/8/8/8/4B3/4K3 w - - 0 1");
+        assert_eq!(chessboard.try_is_piece_pinned(sq("e2")), Ok(true));
+        let pin_data = chessboard.try_calculate_pin_data(sq("e2"));
+        assert_eq!(pin_data, Ok((bb(&["e8"]), bb(&["e2", "e3", "e4", "e5", "e6", "e7", "e8"]))));
+        // a second blocker on the ray means no pin
+        let chessboard = board("4r2k/8/8/8/4P3/8/4B3/4K3 w - - 0 1");
+        assert_eq!(chessboard.try_is_piece_pinned(sq("e2")), Ok(false));
+        assert_eq!(chessboard.try_calculate_pin_data(sq("e2")), Ok((BitBoard::ZERO, BitBoard::ZERO)));
+        // still pinned while the king is in check from elsewhere
+        let chessboard = board("4r2k/8/8/8/8/8/4B3/4K2r w - - 0 1");
+        assert_eq!(chessboard.try_is_piece_pinned(sq("e2")), Ok(true));
+        // diagonal pin against the side not to move
+        let chessboard = board("7k/6n1/8/8/8/8/1B6/K7 w - - 0 1");
+        assert_eq!(chessboard.try_is_piece_pinned(sq("g7")), Ok(true));
+        assert_eq!(chessboard.try_is_piece_pinned(sq("b2")), Ok(false));
+        let e1 = sq("e1");
+        assert_eq!(board("4r2k/8/8/8/8/8/4B3/4K3 w - - 0 1").try_is_piece_pinned(e1), Err(ChessError::KingAsPiece(e1)));
+    }
+
+    #[test]
+    fn castling_rights() {
+        let castle = |fen: &str, side: Side| {
+            let chessboard = board(fen);
+            (chessboard.try_is_able_kingside_castle(side), chessboard.try_is_able_queenside_castle(side))
+        };
+        assert_eq!(castle("k7/8/8/8/8/8/8/R3K2R w KQ - 0 1", Side::White), (Ok(true), Ok(true)));
+        // no castling out of check
+        assert_eq!(castle("k3r3/8/8/8/8/8/8/R3K2R w KQ - 0 1", Side::White), (Ok(false), Ok(false)));
+        // or through an attacked square
+        assert_eq!(castle("k4r2/8/8/8/8/8/8/R3K2R w KQ - 0 1", Side::White), (Ok(false), Ok(true)));
+        assert_eq!(castle("k1r5/8/8/8/8/8/8/R3K2R w KQ - 0 1", Side::White), (Ok(true), Ok(false)));
+        // an attacked b-file square doesn't matter, the king never crosses it
+        assert_eq!(castle("kr6/8/8/8/8/8/8/R3K2R w KQ - 0 1", Side::White), (Ok(true), Ok(true)));
+        assert_eq!(castle("r3k2r/8/8/8/8/8/8/1R4K1 b kq - 0 1", Side::Black), (Ok(true), Ok(true)));
+        assert_eq!(castle("r3k2r/8/8/8/8/8/8/1R4K1 b - - 0 1", Side::Black), (Ok(false), Ok(false)));
+        assert_eq!(castle("8/8/8/8/8/8/8/R6R w KQ - 0 1", Side::White).0, Err(ChessError::KingNotFound(Side::White)));
+    }
+
+    #[test]
+    fn pawn_attacks() {
+        // a black pawn on d5 attacks c4 and e4, not the squares it would attack as a white pawn
+        let chessboard = board("4k3/8/8/3p4/8/8/8/4K3 w - - 0 1");
+        for (square, attacked) in [("c4", true), ("e4", true), ("d4", false), ("c6", false), ("e6", false)] {
+            assert_eq!(chessboard.is_square_attacked(sq(square), Side::Black), attacked, "{}", square);
+            assert_eq!(chessboard.is_square_attacked_removed_piece(sq(square), Side::White, sq("e1")), attacked);
+        }
+        // and the same for a white pawn on d4
+        let chessboard = board("4k3/8/8/8/3P4/8/8/4K3 w - - 0 1");
+        for (square, attacked) in [("c5", true), ("e5", true), ("d5", false), ("c3", false), ("e3", false)] {
+            assert_eq!(chessboard.is_square_attacked(sq(square), Side::White), attacked, "{}", square);
+            assert_eq!(chessboard.is_square_attacked_removed_piece(sq(square), Side::Black, sq("e8")), attacked);
+        }
+    }
+
+    // the attack queries stay usable in const contexts unless the bmi2 backend is selected
+    #[cfg(not(feature = "bmi2"))]
+    #[test]
+    fn attack_queries_are_const() {
+        const START: ChessBoard = ChessBoard::start_pos();
+        // d3, covered by the c2 and e2 pawns
+        const ATTACKERS: BitBoard = START.attackers(Square::new(20), Side::White);
+        const IN_CHECK: bool = START.is_king_in_check(Side::White);
+        const QUEEN: BitBoard = get_queen_attack(Square::new(20), BitBoard::ZERO);
+        assert_eq!(ATTACKERS, bb(&["c2", "e2"]));
+        assert!(IN_CHECK == false);
+        assert_eq!(QUEEN.count_ones(), 25);
+    }
+
+    #[test]
+    fn king_in_check_of_either_side() {
+        // white to move, but it's black's king that is attacked
+        let chessboard = board("4k3/8/8/8/8/8/4R3/4K3 w - - 0 1");
+        assert_eq!(chessboard.try_is_king_in_check(Side::Black), Ok(true));
+        assert_eq!(chessboard.try_is_king_in_check(Side::White), Ok(false));
+        let chessboard = board("4k3/8/8/8/8/8/4r3/4K3 w - - 0 1");
+        assert_eq!(chessboard.try_is_king_in_check(Side::White), Ok(true));
+        assert_eq!(chessboard.try_is_king_in_check(Side::Black), Ok(false));
+    }
+
+    #[test]
+    fn draw() {
+        let chessboard = ChessBoard::start_pos();
+        let drawn = chessboard.draw(false, false);
+        assert!(drawn.starts_with("8 r n b q k b n r\n7 p p p p p p p p\n6 . . . . . . . .\n"));
+        assert!(drawn.ends_with("1 R N B Q K B N R\n  a b c d e f g h\n"));
+        let flipped = chessboard.draw(true, true);
+        assert!(flipped.starts_with("1 ♜ ♞ ♝ ♚ ♛ ♝ ♞ ♜\n"));
+        assert!(flipped.ends_with("  h g f e d c b a\n"));
+    }
+}
@@ -0,0 +1,241 @@
+use crate::bitboard::*;
+
+/* runtime magic number search and verification */
+
+// the attack tables are indexed with the magic numbers in data/magics.rs, this module
+// makes it possible to search for new ones, and to audit the existing ones against the
+// naive (ray-walking) attack functions.
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Slider {
+    Bishop,
+    Rook,
+}
+
+// plain magics index with (occ & mask) * magic >> shift
+// black magics index with (occ | !mask) * magic >> shift, the lowest index used need not be zero
+// so tables for different squares can be packed closer together
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MagicKind {
+    Plain,
+    Black,
+}
+
+impl Slider {
+    // relevant occupancy mask (edges excluded)
+    pub const fn mask(&self, square: usize) -> u64 {
+        match self {
+            Slider::Bishop => BISHOP_MBB_MASK[square].to_u64(),
+            Slider::Rook => ROOK_MBB_MASK[square].to_u64(),
+        }
+    }
+
+    pub const fn naive_attack(&self, square: usize, blockers: u64) -> u64 {
+        match self {
+            Slider::Bishop => naive_bishop_attack(square, BitBoard::new(blockers)).to_u64(),
+            Slider::Rook => naive_rook_attack(square, BitBoard::new(blockers)).to_u64(),
+        }
+    }
+
+    // the magic number currently hard-coded in data/magics.rs
+    pub const fn magic(&self, square: usize) -> u64 {
+        match self {
+            Slider::Bishop => BISHOP_MAGICS[square],
+            Slider::Rook => ROOK_MAGICS[square],
+        }
+    }
+
+    // the largest relevant occupancy (a rook in a corner, a bishop in the centre), a wider index
+    // can't separate anything more
+    pub const fn max_bits(&self) -> usize {
+        match self {
+            Slider::Bishop => 9,
+            Slider::Rook => 12,
+        }
+    }
+
+    // the index size (in bits) currently hard-coded in data/magics.rs
+    pub const fn occ_bitcount(&self, square: usize) -> usize {
+        match self {
+            Slider::Bishop => BISHOP_OCC_BITCOUNT[square],
+            Slider::Rook => ROOK_OCC_BITCOUNT[square],
+        }
+    }
+}
+
+/* ==== prng ==== */
+
+// xorshift64*, good enough for magic searching and reproducible from a seed
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MagicRng {
+    state: u64,
+}
+
+impl MagicRng {
+    pub const fn new(seed: u64) -> Self {
+        // xorshift state must never be zero
+        match seed {
+            0 => MagicRng { state: 0x9E3779B97F4A7C15 },
+            _ => MagicRng { state: seed },
+        }
+    }
+
+    pub const fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545F4914F6CDD1D)
+    }
+
+    // magic candidates with few set bits are far more likely to work
+    pub const fn sparse_u64(&mut self) -> u64 {
+        self.next_u64() & self.next_u64() & self.next_u64()
+    }
+}
+
+/* ==== search and verification ==== */
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MagicEntry {
+    pub square: usize,
+    pub magic: u64,
+    pub bits: usize,
+    pub kind: MagicKind,
+    pub min_index: usize,
+    pub max_index: usize,
+}
+
+impl MagicEntry {
+    // number of table entries actually needed for this square
+    pub const fn table_size(&self) -> usize {
+        self.max_index - self.min_index + 1
+    }
+}
+
+pub const fn magic_index_of(kind: MagicKind, magic: u64, mask: u64, blockers: u64, bits: usize) -> usize {
+    let occ = match kind {
+        MagicKind::Plain => blockers & mask,
+        MagicKind::Black => blockers | !mask,
+    };
+    (occ.wrapping_mul(magic) >> (64 - bits)) as usize
+}
+
+// checks magic against every relevant occupancy of square, returns None on a destructive collision
+// collisions that map to the same attack set are fine, which is what allows bits < mask bitcount
+// bits above slider.max_bits() are rejected too, before the table is allocated
+pub fn verify_magic(slider: Slider, square: usize, magic: u64, bits: usize, kind: MagicKind) -> Option<MagicEntry> {
+    assert!(square < 64, "verify_magic error: square out of range!");
+    assert!(0 < bits, "verify_magic error: invalid index size!");
+    if bits > slider.max_bits() {
+        return None;
+    }
+    let mask = slider.mask(square);
+    let mask_bitcount = mask.count_ones() as usize;
+    let mut table: Vec<Option<u64>> = vec![None; 1 << bits];
+    let mut min_index: usize = usize::MAX;
+    let mut max_index: usize = 0;
+
+    for j in 0..(1usize << mask_bitcount) {
+        let blockers = compute_occ_bb(j, mask_bitcount, BitBoard::new(mask)).to_u64();
+        let attack = slider.naive_attack(square, blockers);
+        let m = magic_index_of(kind, magic, mask, blockers, bits);
+        match table[m] {
+            None => table[m] = Some(attack),
+            Some(x) if x == attack => {}
+            Some(_) => return None,
+        }
+        min_index = min_index.min(m);
+        max_index = max_index.max(m);
+    }
+    Some(MagicEntry { square, magic, bits, kind, min_index, max_index })
+}
+
+// tries up to max_tries random candidates, returns the first magic that verifies
+pub fn find_magic(
+    slider: Slider,
+    square: usize,
+    bits: usize,
+    kind: MagicKind,
+    rng: &mut MagicRng,
+    max_tries: usize,
+) -> Option<MagicEntry> {
+    if bits > slider.max_bits() {
+        return None;
+    }
+    // cheap filter: the top byte of the fullest occupancy times magic should be well populated
+    let full = match kind {
+        MagicKind::Plain => slider.mask(square),
+        MagicKind::Black => !slider.mask(square),
+    };
+    for _ in 0..max_tries {
+        let magic = rng.sparse_u64();
+        if (full.wrapping_mul(magic) & 0xFF00000000000000).count_ones() < 6 {
+            continue;
+        }
+        if let Some(entry) = verify_magic(slider, square, magic, bits, kind) {
+            return Some(entry);
+        }
+    }
+    None
+}
+
+// searches all 64 squares with the hard-coded index sizes, deterministic for a given seed
+pub fn find_magics(slider: Slider, kind: MagicKind, seed: u64, max_tries: usize) -> Vec<Option<MagicEntry>> {
+    let mut rng = MagicRng::new(seed);
+    (0..64).map(|square| find_magic(slider, square, slider.occ_bitcount(square), kind, &mut rng, max_tries)).collect()
+}
+
+// audits the hard-coded magics, None marks a square whose magic collides
+pub fn verify_magics(slider: Slider) -> Vec<Option<MagicEntry>> {
+    (0..64)
+        .map(|square| {
+            verify_magic(slider, square, slider.magic(square), slider.occ_bitcount(square), MagicKind::Plain)
+        })
+        .collect()
+}
+
+// total number of table entries needed if every square's table is trimmed to the indices it uses
+pub fn total_table_size(entries: &[Option<MagicEntry>]) -> Option<usize> {
+    entries.iter().map(|entry| entry.map(|x| x.table_size())).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hard_coded_magics_verify() {
+        for slider in [Slider::Bishop, Slider::Rook] {
+            let entries = verify_magics(slider);
+            assert!(entries.iter().all(|entry| entry.is_some()), "{:?}", slider);
+            // plain magics with the hard-coded sizes never need more than the full table
+            assert!(total_table_size(&entries).unwrap() <= (0..64).map(|x| 1 << slider.occ_bitcount(x)).sum());
+        }
+    }
+
+    #[test]
+    fn search_finds_verifiable_magics() {
+        let mut rng = MagicRng::new(0x5EED);
+        for (slider, square) in [(Slider::Bishop, 0), (Slider::Bishop, 27), (Slider::Rook, 9), (Slider::Rook, 36)] {
+            for kind in [MagicKind::Plain, MagicKind::Black] {
+                let bits = slider.occ_bitcount(square);
+                let entry = find_magic(slider, square, bits, kind, &mut rng, 1_000_000).unwrap();
+                assert_eq!(verify_magic(slider, square, entry.magic, bits, kind), Some(entry));
+                assert!(entry.table_size() <= 1 << bits);
+            }
+        }
+    }
+
+    #[test]
+    fn verify_rejects_bad_magics() {
+        // every occupancy maps to index 0, but the attacks differ
+        assert_eq!(verify_magic(Slider::Rook, 0, 0, 12, MagicKind::Plain), None);
+        assert_eq!(verify_magic(Slider::Bishop, 27, 0, 9, MagicKind::Black), None);
+        // too few index bits for the hard-coded magic
+        assert_eq!(verify_magic(Slider::Rook, 0, ROOK_MAGICS[0], 4, MagicKind::Plain), None);
+        // index sizes no slider needs, rejected without allocating the table
+        assert_eq!(verify_magic(Slider::Rook, 0, ROOK_MAGICS[0], 13, MagicKind::Plain), None);
+        assert_eq!(verify_magic(Slider::Bishop, 27, BISHOP_MAGICS[27], 63, MagicKind::Plain), None);
+        assert_eq!(find_magic(Slider::Bishop, 27, 10, MagicKind::Plain, &mut MagicRng::new(1), 1000), None);
+    }
+}
@@ -0,0 +1,126 @@
+use std::io::{self, BufRead, Write};
+
+use chessbb::ChessBoard;
+
+/* ==== chessbb repl ==== */
+
+// a line based shell for poking at positions, `chessbb --ascii` draws the board without unicode.
+// commands that need move generation, search or evaluation are recognised, but report that
+// they aren't available yet (see movegen.rs).
+
+const HELP: &str = "\
+commands:
+  fen [FEN]    print the position as fen, or set it
+  startpos     set the starting position
+  display      draw the board
+  flip         draw the board from the other side
+  help         show this message
+  quit         leave
+not available yet: moves, play MOVE, undo, perft N, divide N, eval, go depth N, bench";
+
+// commands that exist but can't run in this version, and what they are waiting on
+const UNSUPPORTED: [(&str, &str); 8] = [
+    ("moves", "move generation"),
+    ("play", "move generation"),
+    ("undo", "move generation"),
+    ("perft", "move generation"),
+    ("divide", "move generation"),
+    ("eval", "an evaluation function"),
+    ("go", "a search"),
+    ("bench", "a search"),
+];
+
+struct Repl {
+    chessboard: ChessBoard,
+    unicode: bool,
+    flipped: bool,
+}
+
+impl Repl {
+    // returns false once the user asks to quit
+    fn execute(&mut self, line: &str, out: &mut impl Write) -> io::Result<bool> {
+        let (command, args) = match line.trim().split_once(char::is_whitespace) {
+            Some((command, args)) => (command, args.trim()),
+            None => (line.trim(), ""),
+        };
+        match command {
+            "" => {}
+            "quit" | "exit" => return Ok(false),
+            "help" => writeln!(out, "{}", HELP)?,
+            "startpos" => self.chessboard = ChessBoard::start_pos(),
+            "fen" if args.is_empty() => writeln!(out, "{}", self.chessboard.to_fen())?,
+            "fen" => match ChessBoard::from_fen(args) {
+                Ok(chessboard) => match chessboard.validate() {
+                    Ok(()) => self.chessboard = chessboard,
+                    Err(errors) => {
+                        for error in errors {
+                            writeln!(out, "illegal position: {}", error)?;
+                        }
+                    }
+                },
+                Err(error) => writeln!(out, "invalid fen: {}", error)?,
+            },
+            "display" | "d" => self.display(out)?,
+            "flip" => {
+                self.flipped = !self.flipped;
+                self.display(out)?;
+            }
+            _ => match UNSUPPORTED.iter().find(|(name, _)| *name == command) {
+                Some((name, missing)) => writeln!(out, "{}: not available yet, chessbb has no {}", name, missing)?,
+                None => writeln!(out, "unknown command: {} (try help)", command)?,
+            },
+        }
+        Ok(true)
+    }
+
+    fn display(&self, out: &mut impl Write) -> io::Result<()> {
+        write!(out, "{}", self.chessboard.draw(self.unicode, self.flipped))?;
+        writeln!(out, "fen: {}", self.chessboard.to_fen())
+    }
+}
+
+fn main() -> io::Result<()> {
+    let unicode = std::env::args().skip(1).all(|arg| arg != "--ascii");
+    let mut repl = Repl { chessboard: ChessBoard::start_pos(), unicode, flipped: false };
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    loop {
+        write!(stdout, "> ")?;
+        stdout.flush()?;
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            break;
+        }
+        if repl.execute(&line, &mut stdout)? == false {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(repl: &mut Repl, line: &str) -> String {
+        let mut out = Vec::new();
+        repl.execute(line, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn commands() {
+        let mut repl = Repl { chessboard: ChessBoard::start_pos(), unicode: false, flipped: false };
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 b - - 0 1";
+        assert_eq!(run(&mut repl, &format!("fen {}", fen)), "");
+        assert_eq!(run(&mut repl, "fen"), format!("{}\n", fen));
+        assert!(run(&mut repl, "fen 4k3/8/8/8/8/8/4R3/4K3 w - - 0 1").starts_with("illegal position"));
+        assert!(run(&mut repl, "fen nonsense").starts_with("invalid fen"));
+        assert_eq!(run(&mut repl, "fen"), format!("{}\n", fen));
+        assert!(run(&mut repl, "flip").starts_with("1 . . . K"));
+        assert!(run(&mut repl, "play e2e4").contains("not available yet"));
+        assert!(run(&mut repl, "startpos").is_empty());
+        assert_eq!(run(&mut repl, "fen"), format!("{}\n", chessbb::START_FEN));
+        assert!(repl.execute("quit", &mut Vec::new()).unwrap() == false);
+    }
+}
@@ -1,4 +1,5 @@
-use std::pin;
+// work in progress, nothing calls into the move generator yet
+#![allow(dead_code, unused_variables, unused_assignments)]
 
 use super::chessmove::*;
 use super::*;
@@ -133,7 +134,7 @@ fn calculate_attacks(cb: &ChessBoard, s: Square, p: PieceType, p_m: BitBoard, c_
     }
 
     let mut moves: Vec<ChessMove> = Vec::new();
-    let mut targets = match piece_type {
+    let targets = match piece_type {
         PieceType::King => get_king_attack(source).bit_and(&friends),
         PieceType::Queen => get_queen_attack(source, blockers).bit_and(&friends.bit_not()),
         PieceType::Knight => get_knight_attack(source).bit_and(&friends.bit_not()),
@@ -148,9 +149,7 @@ fn calculate_attacks(cb: &ChessBoard, s: Square, p: PieceType, p_m: BitBoard, c_
     //pawn rules are complex, best handled separately
     assert!(piece_type != PieceType::Pawn);
 
-    while targets.is_not_zero() {
-        let target = targets.lsb_square().unwrap();
-
+    for target in targets {
         //just in case...
         assert!(piece_type != PieceType::Pawn);
         assert!(piece_type != PieceType::Knight || pin_mask.is_zero());
@@ -158,7 +157,6 @@ fn calculate_attacks(cb: &ChessBoard, s: Square, p: PieceType, p_m: BitBoard, c_
         //NOTE (special cases)
         //king: cannot move to a square under attack
         if piece_type == PieceType::King && cb.is_square_attacked_removed_piece(target, side, cb.king_square()) {
-            targets = targets.pop_bit(target);
             continue;
         };
 
@@ -166,7 +164,6 @@ fn calculate_attacks(cb: &ChessBoard, s: Square, p: PieceType, p_m: BitBoard, c_
         //pinned logic
         //only consider moves along pinning ray if pinned
         if pin_mask.is_not_zero() && pin_mask.nth_is_zero(target) {
-            targets = targets.pop_bit(target);
             //FIXME is this necessary?
             //assert!(piece_type != PieceType::Knight);
             continue;
@@ -175,13 +172,11 @@ fn calculate_attacks(cb: &ChessBoard, s: Square, p: PieceType, p_m: BitBoard, c_
         //checked logic
         //only consider moves along checking ray if in check
         if check_mask.is_not_zero() && check_mask.nth_is_zero(target) {
-            targets = targets.pop_bit(target);
             continue;
         }
 
         //append moves
         moves.push(ChessMove::new(source, target, MoveType::Normal));
-        targets = targets.pop_bit(target);
     }
     return moves;
 }
@@ -235,7 +230,7 @@ fn calculate_pawn_moves(
             //FIXME assumption checkers_count == 1
             // can only move one-square if not in check, or blocks check
             if check_mask.is_zero() || check_mask.nth_is_not_zero(target) {
-                match (ROWS[target.to_index()] == promotion_row) {
+                match ROWS[target.to_index()] == promotion_row {
                     true => moves.append(&mut ChessMove::promotions(source, target).to_vec()),
                     false => moves.push(ChessMove::new(source, target, MoveType::Normal)),
                 }
@@ -280,7 +275,7 @@ fn calculate_pawn_moves(
                 if check_mask.is_zero() || (check_mask.nth_is_not_zero(attack)) {
                     //can only attack a square if not pinned or attack is along a pin-ray
                     if pin_mask.is_zero() || pin_mask.nth_is_not_zero(attack) {
-                        match (ROWS[attack.to_index()] == promotion_row) {
+                        match ROWS[attack.to_index()] == promotion_row {
                             true => moves.append(&mut ChessMove::promotions(source, attack).to_vec()),
                             false => moves.push(ChessMove::new(source, attack, MoveType::Normal)),
                         }
@@ -307,7 +302,7 @@ fn calculate_pawn_moves(
             // . . . | .
             // . . . x .
 
-            let row_bb = BitBoard::new(0b11111111u64 << (8 * ROWS[source.to_index()]));
+            let row_bb = BitBoard::row_mask(source);
 
             let enemy_rook_index;
             let enemy_pawn_index;
@@ -381,15 +376,13 @@ const fn king_moves(chessboard: &ChessBoard) -> Vec<ChessMove> {
 }
 
 //queen, rook, bishops
-fn ray_moves(source: Square, mut attacks: BitBoard) -> Vec<ChessMove> {
+fn ray_moves(source: Square, attacks: BitBoard) -> Vec<ChessMove> {
     //let mut attacks: BitBoard = get_queen_attack(source, blockers).bit_and(&friends.bit_not());
     let mut moves: Vec<ChessMove> = Vec::<ChessMove>::new();
-    while attacks.is_not_zero() {
-        let target = attacks.lsb_square().expect("attacks should not be empty");
+    for target in attacks {
         // only consider moves along pinning ray if pinned
         // only consider moves along checking ray if in check
         moves.push(ChessMove::new(source, target, MoveType::Normal));
-        attacks = attacks.pop_bit(target);
     }
     return moves;
 }
@@ -0,0 +1,553 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+use crate::ChessBoard;
+use crate::bitboard::*;
+use crate::error::ChessError;
+use crate::square::Square;
+
+/* ==== nnue ==== */
+
+// inference for networks trained elsewhere: a feature transformer (inputs -> hidden, one
+// accumulator per perspective), clipped relu, and a single output neuron over both accumulators,
+// side to move first. everything is integer, so the scalar and simd paths agree exactly.
+//
+// features are keyed by the perspective's own king square and (Side, PieceType), seen from that
+// perspective: black flips the ranks (square ^ 56) of its king and of every piece, and a piece's
+// colour is relative (0 for the perspective's own pieces, 1 for the other side's).
+//   HalfKP  (king * 10 + type * 2 + colour) * 64 + square, type Pawn..Queen, kings aren't features
+//   HalfKA  (king * 12 + type * 2 + colour) * 64 + square, type Pawn..King
+// squares are board indices, 0 = h1, 63 = a8 (see bitboard.rs).
+//
+// file format, little endian:
+//  0..4   b"CBNN"
+//  4      format version
+//  5      feature set, 0 = HalfKP, 1 = HalfKA
+//  6..8   hidden size (u16), a non-zero multiple of 16, at most MAX_HIDDEN
+//  then   feature weights (i16, inputs * hidden, feature by feature), feature biases (i16, hidden),
+//         output weights (i16, 2 * hidden), output bias (i32)
+// the accumulator is quantised by QA, the output weights by QB and the output bias by QA * QB.
+
+pub const QA: i32 = 255;
+pub const QB: i32 = 64;
+// centipawns per unit of network output
+pub const SCALE: i32 = 400;
+
+const MAGIC: [u8; 4] = *b"CBNN";
+const VERSION: u8 = 1;
+// i16 lanes in an avx2 register, the hidden size must be a multiple of this
+const LANES: usize = 16;
+// well above the hidden sizes in use, a HalfKA net this wide is already 400 MB of feature weights
+pub const MAX_HIDDEN: usize = 4096;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FeatureSet {
+    HalfKP,
+    HalfKA,
+}
+
+impl FeatureSet {
+    // feature kinds per king square
+    const fn kinds(&self) -> usize {
+        match self {
+            FeatureSet::HalfKP => 10,
+            FeatureSet::HalfKA => 12,
+        }
+    }
+
+    pub const fn inputs(&self) -> usize {
+        64 * self.kinds() * 64
+    }
+
+    // None for pieces that aren't features (kings in HalfKP)
+    pub fn index(&self, perspective: Side, king: Square, piece: (Side, PieceType), square: Square) -> Option<usize> {
+        if matches!(self, FeatureSet::HalfKP) && piece.1 == PieceType::King {
+            return None;
+        }
+        let flip = match perspective {
+            Side::White => 0,
+            Side::Black => 56,
+        };
+        let kind = piece.1 as usize * 2 + (piece.0 != perspective) as usize;
+        Some(((king.to_index() ^ flip) * self.kinds() + kind) * 64 + (square.to_index() ^ flip))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Network {
+    features: FeatureSet,
+    hidden: usize,
+    ft_weights: Vec<i16>,
+    ft_biases: Vec<i16>,
+    out_weights: Vec<i16>,
+    out_bias: i32,
+}
+
+// reads through take() rather than into a buffer of the full size, so a truncated file fails
+// after reading what is there instead of allocating for what the header claims
+fn read_i16s<R: Read>(reader: &mut R, len: usize) -> io::Result<Vec<i16>> {
+    let mut bytes: Vec<u8> = Vec::new();
+    reader.take(2 * len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != 2 * len {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "network file is truncated"));
+    }
+    Ok(bytes.chunks_exact(2).map(|x| i16::from_le_bytes([x[0], x[1]])).collect())
+}
+
+fn write_i16s<W: Write>(writer: &mut W, values: &[i16]) -> io::Result<()> {
+    let bytes: Vec<u8> = values.iter().flat_map(|x| x.to_le_bytes()).collect();
+    writer.write_all(&bytes)
+}
+
+impl Network {
+    pub fn features(&self) -> FeatureSet {
+        self.features
+    }
+
+    pub fn hidden(&self) -> usize {
+        self.hidden
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Network> {
+        let mut header: [u8; 8] = [0; 8];
+        reader.read_exact(&mut header)?;
+        if header[0..4] != MAGIC || header[4] != VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a chessbb network"));
+        }
+        let features = match header[5] {
+            0 => FeatureSet::HalfKP,
+            1 => FeatureSet::HalfKA,
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "unknown feature set")),
+        };
+        let hidden = u16::from_le_bytes([header[6], header[7]]) as usize;
+        if hidden == 0 || hidden % LANES != 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "hidden size must be a multiple of 16"));
+        }
+        if hidden > MAX_HIDDEN {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "hidden size is too large"));
+        }
+        let ft_weights = read_i16s(&mut reader, features.inputs() * hidden)?;
+        let ft_biases = read_i16s(&mut reader, hidden)?;
+        let out_weights = read_i16s(&mut reader, 2 * hidden)?;
+        let mut out_bias: [u8; 4] = [0; 4];
+        reader.read_exact(&mut out_bias)?;
+        Ok(Network { features, hidden, ft_weights, ft_biases, out_weights, out_bias: i32::from_le_bytes(out_bias) })
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let features: u8 = match self.features {
+            FeatureSet::HalfKP => 0,
+            FeatureSet::HalfKA => 1,
+        };
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&[VERSION, features])?;
+        writer.write_all(&(self.hidden as u16).to_le_bytes())?;
+        write_i16s(&mut writer, &self.ft_weights)?;
+        write_i16s(&mut writer, &self.ft_biases)?;
+        write_i16s(&mut writer, &self.out_weights)?;
+        writer.write_all(&self.out_bias.to_le_bytes())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Network> {
+        Network::read(BufReader::new(File::open(path)?))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+    fn weights(&self, feature: usize) -> &[i16] {
+        &self.ft_weights[feature * self.hidden..(feature + 1) * self.hidden]
+    }
+
+    // one-off evaluation from scratch, in centipawns from the side to move's point of view
+    pub fn evaluate(&self, chessboard: &ChessBoard) -> Result<i32, ChessError> {
+        Ok(Accumulator::new(self, chessboard)?.evaluate(self, chessboard.side_to_move))
+    }
+}
+
+/* ==== accumulators ==== */
+
+// a piece appearing on or leaving a square, a move is a handful of these (e.g. a capture is
+// Remove(captured), Remove(mover, from), Add(mover, to)). to unmake, apply the inverses in reverse
+// order, or keep a copy of the accumulator per ply
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Delta {
+    Add((Side, PieceType), Square),
+    Remove((Side, PieceType), Square),
+}
+
+impl Delta {
+    pub const fn inverse(&self) -> Delta {
+        match *self {
+            Delta::Add(piece, square) => Delta::Remove(piece, square),
+            Delta::Remove(piece, square) => Delta::Add(piece, square),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Accumulator {
+    // white's perspective first
+    values: [Vec<i16>; 2],
+    king_squares: [Square; 2],
+}
+
+const fn perspective_index(side: Side) -> usize {
+    match side {
+        Side::White => 0,
+        Side::Black => 1,
+    }
+}
+
+fn king_square(chessboard: &ChessBoard, side: Side) -> Result<Square, ChessError> {
+    match chessboard.piece_bbs[cp_index((side, PieceType::King))].lsb_square() {
+        Some(x) => Ok(x),
+        None => Err(ChessError::KingNotFound(side)),
+    }
+}
+
+fn add_weights(values: &mut [i16], weights: &[i16]) {
+    #[cfg(target_arch = "x86_64")]
+    if has_avx2() {
+        // safety: avx2 support was checked above
+        return unsafe { add_weights_avx2(values, weights) };
+    }
+    add_weights_scalar(values, weights)
+}
+
+fn sub_weights(values: &mut [i16], weights: &[i16]) {
+    #[cfg(target_arch = "x86_64")]
+    if has_avx2() {
+        // safety: avx2 support was checked above
+        return unsafe { sub_weights_avx2(values, weights) };
+    }
+    sub_weights_scalar(values, weights)
+}
+
+// wrapping, like the i16 lanes of a simd add
+fn add_weights_scalar(values: &mut [i16], weights: &[i16]) {
+    values.iter_mut().zip(weights).for_each(|(x, w)| *x = x.wrapping_add(*w));
+}
+
+fn sub_weights_scalar(values: &mut [i16], weights: &[i16]) {
+    values.iter_mut().zip(weights).for_each(|(x, w)| *x = x.wrapping_sub(*w));
+}
+
+// values and weights are hidden i16s, a multiple of LANES
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+fn add_weights_avx2(values: &mut [i16], weights: &[i16]) {
+    for (x, w) in values.chunks_exact_mut(LANES).zip(weights.chunks_exact(LANES)) {
+        // safety: both chunks are 16 i16s, loadu and storeu have no alignment requirement
+        unsafe {
+            let sum = _mm256_add_epi16(
+                _mm256_loadu_si256(x.as_ptr() as *const __m256i),
+                _mm256_loadu_si256(w.as_ptr() as *const __m256i),
+            );
+            _mm256_storeu_si256(x.as_mut_ptr() as *mut __m256i, sum);
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+fn sub_weights_avx2(values: &mut [i16], weights: &[i16]) {
+    for (x, w) in values.chunks_exact_mut(LANES).zip(weights.chunks_exact(LANES)) {
+        // safety: both chunks are 16 i16s, loadu and storeu have no alignment requirement
+        unsafe {
+            let difference = _mm256_sub_epi16(
+                _mm256_loadu_si256(x.as_ptr() as *const __m256i),
+                _mm256_loadu_si256(w.as_ptr() as *const __m256i),
+            );
+            _mm256_storeu_si256(x.as_mut_ptr() as *mut __m256i, difference);
+        }
+    }
+}
+
+impl Accumulator {
+    pub fn new(network: &Network, chessboard: &ChessBoard) -> Result<Accumulator, ChessError> {
+        let mut accumulator = Accumulator {
+            values: [network.ft_biases.clone(), network.ft_biases.clone()],
+            king_squares: [Square::new(0); 2],
+        };
+        accumulator.refresh(network, chessboard, Side::White)?;
+        accumulator.refresh(network, chessboard, Side::Black)?;
+        Ok(accumulator)
+    }
+
+    // rebuilds one perspective from the piece bitboards
+    pub fn refresh(&mut self, network: &Network, chessboard: &ChessBoard, perspective: Side) -> Result<(), ChessError> {
+        let king = king_square(chessboard, perspective)?;
+        let p = perspective_index(perspective);
+        self.values[p].copy_from_slice(&network.ft_biases);
+        for side in [Side::White, Side::Black] {
+            for piece in PieceType::iterator() {
+                for square in chessboard.piece_bbs[cp_index((side, *piece))].iter() {
+                    if let Some(i) = network.features.index(perspective, king, (side, *piece), square) {
+                        add_weights(&mut self.values[p], network.weights(i));
+                    }
+                }
+            }
+        }
+        self.king_squares[p] = king;
+        Ok(())
+    }
+
+    // deltas turn the previous position into chessboard. a perspective whose own king moved has
+    // every feature re-keyed, so it is refreshed from chessboard instead
+    pub fn apply(&mut self, network: &Network, chessboard: &ChessBoard, deltas: &[Delta]) -> Result<(), ChessError> {
+        for perspective in [Side::White, Side::Black] {
+            let king = king_square(chessboard, perspective)?;
+            let p = perspective_index(perspective);
+            if king != self.king_squares[p] {
+                self.refresh(network, chessboard, perspective)?;
+                continue;
+            }
+            for delta in deltas {
+                match *delta {
+                    Delta::Add(piece, square) => {
+                        if let Some(i) = network.features.index(perspective, king, piece, square) {
+                            add_weights(&mut self.values[p], network.weights(i));
+                        }
+                    }
+                    Delta::Remove(piece, square) => {
+                        if let Some(i) = network.features.index(perspective, king, piece, square) {
+                            sub_weights(&mut self.values[p], network.weights(i));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // centipawns from the side to move's point of view, uses avx2 when the cpu supports it
+    pub fn evaluate(&self, network: &Network, side_to_move: Side) -> i32 {
+        #[cfg(target_arch = "x86_64")]
+        if has_avx2() {
+            // safety: avx2 support was checked above
+            return unsafe { self.evaluate_avx2(network, side_to_move) };
+        }
+        self.evaluate_scalar(network, side_to_move)
+    }
+
+    fn perspectives(&self, side_to_move: Side) -> (&[i16], &[i16]) {
+        let us = perspective_index(side_to_move);
+        (&self.values[us], &self.values[1 - us])
+    }
+
+    pub fn evaluate_scalar(&self, network: &Network, side_to_move: Side) -> i32 {
+        let (us, them) = self.perspectives(side_to_move);
+        let (us_weights, them_weights) = network.out_weights.split_at(network.hidden);
+        let mut sum: i32 = 0;
+        for (values, weights) in [(us, us_weights), (them, them_weights)] {
+            for (x, w) in values.iter().zip(weights) {
+                sum = sum.wrapping_add((*x as i32).clamp(0, QA) * *w as i32);
+            }
+        }
+        output(sum, network.out_bias)
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2")]
+    fn evaluate_avx2(&self, network: &Network, side_to_move: Side) -> i32 {
+        let (us, them) = self.perspectives(side_to_move);
+        let (us_weights, them_weights) = network.out_weights.split_at(network.hidden);
+        let (zero, qa) = (_mm256_setzero_si256(), _mm256_set1_epi16(QA as i16));
+        let mut sum = _mm256_setzero_si256();
+        for (values, weights) in [(us, us_weights), (them, them_weights)] {
+            for (x, w) in values.chunks_exact(LANES).zip(weights.chunks_exact(LANES)) {
+                // safety: both chunks are 16 i16s, loadu has no alignment requirement
+                let (x, w) = unsafe {
+                    (_mm256_loadu_si256(x.as_ptr() as *const __m256i), _mm256_loadu_si256(w.as_ptr() as *const __m256i))
+                };
+                let clipped = _mm256_min_epi16(_mm256_max_epi16(x, zero), qa);
+                // clipped * w fits an i32, madd sums neighbouring pairs of them
+                sum = _mm256_add_epi32(sum, _mm256_madd_epi16(clipped, w));
+            }
+        }
+        let mut lanes: [i32; 8] = [0; 8];
+        // safety: lanes is 32 bytes
+        unsafe { _mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, sum) };
+        let sum = lanes.iter().fold(0i32, |acc, x| acc.wrapping_add(*x));
+        output(sum, network.out_bias)
+    }
+}
+
+fn output(sum: i32, bias: i32) -> i32 {
+    (sum.wrapping_add(bias) as i64 * SCALE as i64 / (QA * QB) as i64) as i32
+}
+
+// picks the simd paths for accumulator updates and the output layer. the hidden size is a
+// multiple of 16, so the avx2 code needs no scalar tail, and without avx2 everything runs scalar
+pub fn has_avx2() -> bool {
+    #[cfg(target_arch = "x86_64")]
+    return std::arch::is_x86_feature_detected!("avx2");
+    #[cfg(not(target_arch = "x86_64"))]
+    return false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::magic::MagicRng;
+    use crate::square::sq;
+
+    // small random weights, like a real net they keep the accumulator well inside i16
+    fn random_network(features: FeatureSet, hidden: usize, seed: u64) -> Network {
+        let mut rng = MagicRng::new(seed);
+        let mut random = |len: usize, range: i16| -> Vec<i16> {
+            (0..len).map(|_| (rng.next_u64() % (2 * range as u64 + 1)) as i16 - range).collect()
+        };
+        Network {
+            features,
+            hidden,
+            ft_weights: random(features.inputs() * hidden, 64),
+            ft_biases: random(hidden, 128),
+            out_weights: random(2 * hidden, 127),
+            out_bias: 1234,
+        }
+    }
+
+    fn board(fen: &str) -> ChessBoard {
+        ChessBoard::from_fen(fen).unwrap()
+    }
+
+    #[test]
+    fn feature_index() {
+        let features = FeatureSet::HalfKP;
+        assert_eq!(features.index(Side::White, sq("e1"), (Side::White, PieceType::King), sq("e1")), None);
+        // a white pawn seen by white is a black pawn on the mirrored square seen by black
+        let white = FeatureSet::HalfKA.index(Side::White, sq("e1"), (Side::White, PieceType::Pawn), sq("d4"));
+        let black = FeatureSet::HalfKA.index(Side::Black, sq("e8"), (Side::Black, PieceType::Pawn), sq("d5"));
+        assert_eq!(white, black);
+        let last = features.index(Side::White, sq("a8"), (Side::Black, PieceType::Queen), sq("a8"));
+        assert_eq!(last, Some(features.inputs() - 1));
+    }
+
+    #[test]
+    fn incremental_matches_refresh() {
+        for features in [FeatureSet::HalfKP, FeatureSet::HalfKA] {
+            let network = random_network(features, 32, 0x5EED);
+            let middlegame = "r3k2r/pp3ppp/8/3p4/4P3/8/PPP2PPP/R3K2R w KQkq - 0 1";
+            let endgame = "1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1";
+            let moves: [(&str, &str, Vec<Delta>); 4] = [
+                // exd5
+                (
+                    middlegame,
+                    "r3k2r/pp3ppp/8/3P4/8/8/PPP2PPP/R3K2R b KQkq - 0 1",
+                    vec![
+                        Delta::Remove((Side::Black, PieceType::Pawn), sq("d5")),
+                        Delta::Remove((Side::White, PieceType::Pawn), sq("e4")),
+                        Delta::Add((Side::White, PieceType::Pawn), sq("d5")),
+                    ],
+                ),
+                // white castles kingside, white's perspective is refreshed
+                (
+                    middlegame,
+                    "r3k2r/pp3ppp/8/3p4/4P3/8/PPP2PPP/R4RK1 b kq - 1 1",
+                    vec![
+                        Delta::Remove((Side::White, PieceType::King), sq("e1")),
+                        Delta::Add((Side::White, PieceType::King), sq("g1")),
+                        Delta::Remove((Side::White, PieceType::Rook), sq("h1")),
+                        Delta::Add((Side::White, PieceType::Rook), sq("f1")),
+                    ],
+                ),
+                // axb8=Q
+                (
+                    endgame,
+                    "1Q2k3/8/8/8/8/8/8/4K3 b - - 0 1",
+                    vec![
+                        Delta::Remove((Side::Black, PieceType::Knight), sq("b8")),
+                        Delta::Remove((Side::White, PieceType::Pawn), sq("a7")),
+                        Delta::Add((Side::White, PieceType::Queen), sq("b8")),
+                    ],
+                ),
+                // a black king move, black's perspective is refreshed
+                (
+                    "1n2k3/P7/8/8/8/8/8/4K3 b - - 0 1",
+                    "1n6/P2k4/8/8/8/8/8/4K3 w - - 1 2",
+                    vec![
+                        Delta::Remove((Side::Black, PieceType::King), sq("e8")),
+                        Delta::Add((Side::Black, PieceType::King), sq("d7")),
+                    ],
+                ),
+            ];
+            for (before, after, deltas) in moves.iter() {
+                let (before, after) = (board(before), board(after));
+                let mut accumulator = Accumulator::new(&network, &before).unwrap();
+                accumulator.apply(&network, &after, deltas).unwrap();
+                assert_eq!(accumulator, Accumulator::new(&network, &after).unwrap(), "{:?} {:?}", features, deltas);
+                // and back again
+                let inverse: Vec<Delta> = deltas.iter().rev().map(|x| x.inverse()).collect();
+                accumulator.apply(&network, &before, &inverse).unwrap();
+                assert_eq!(accumulator, Accumulator::new(&network, &before).unwrap(), "{:?} {:?}", features, deltas);
+            }
+        }
+    }
+
+    #[test]
+    fn scalar_matches_simd() {
+        for features in [FeatureSet::HalfKP, FeatureSet::HalfKA] {
+            let network = random_network(features, 64, 0xC0FFEE);
+            for fen in [
+                crate::START_FEN,
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 0 1",
+            ] {
+                let chessboard = board(fen);
+                let accumulator = Accumulator::new(&network, &chessboard).unwrap();
+                for side in [Side::White, Side::Black] {
+                    let scalar = accumulator.evaluate_scalar(&network, side);
+                    assert_eq!(accumulator.evaluate(&network, side), scalar, "{:?} {}", features, fen);
+                }
+                assert_eq!(network.evaluate(&chessboard), Ok(accumulator.evaluate(&network, chessboard.side_to_move)));
+            }
+        }
+    }
+
+    #[test]
+    fn scalar_matches_simd_updates() {
+        let network = random_network(FeatureSet::HalfKA, 64, 0xABCD);
+        let mut rng = MagicRng::new(99);
+        // large values so the i16 lanes wrap
+        let start: Vec<i16> = (0..network.hidden).map(|_| rng.next_u64() as i16).collect();
+        for feature in [0, 1, 4321, FeatureSet::HalfKA.inputs() - 1] {
+            let (mut scalar, mut simd) = (start.clone(), start.clone());
+            add_weights_scalar(&mut scalar, network.weights(feature));
+            add_weights(&mut simd, network.weights(feature));
+            assert_eq!(simd, scalar, "add {}", feature);
+            sub_weights_scalar(&mut scalar, network.weights(feature));
+            sub_weights(&mut simd, network.weights(feature));
+            assert_eq!(simd, scalar, "sub {}", feature);
+            assert_eq!(simd, start);
+        }
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let network = random_network(FeatureSet::HalfKP, 16, 7);
+        let mut bytes = Vec::new();
+        network.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 8 + 2 * (FeatureSet::HalfKP.inputs() * 16 + 16 + 32) + 4);
+        assert_eq!(Network::read(bytes.as_slice()).unwrap(), network);
+        assert_eq!(Network::read(&bytes[..1000]).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        // 65520 hidden, rejected before anything is allocated
+        let mut huge = bytes[..8].to_vec();
+        huge[6..8].copy_from_slice(&65520u16.to_le_bytes());
+        assert_eq!(Network::read(huge.as_slice()).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        huge[6..8].copy_from_slice(&(MAX_HIDDEN as u16).to_le_bytes());
+        assert_eq!(Network::read(huge.as_slice()).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        bytes[6] = 8;
+        assert_eq!(Network::read(bytes.as_slice()).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        bytes[0] = b'X';
+        assert_eq!(Network::read(bytes.as_slice()).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,379 @@
+use super::*;
+use crate::chessmove::MoveType;
+
+/* ==== move ordering ==== */
+
+// scores generated moves so the search tries the most promising ones first:
+// tt move, winning and equal captures (mvv-lva), quiet promotions by the promoted piece, killers,
+// countermove, quiets by history, and finally the captures and promotions that lose material
+// according to static exchange evaluation.
+
+pub const MAX_PLY: usize = 128;
+
+// piece values for exchange evaluation, indexed by cp_index(piece) % 6 (K, Q, N, B, R, P)
+pub const SEE_VALUES: [i32; 6] = [20000, 900, 320, 330, 500, 100];
+// mvv-lva ranks, same indexing, the king is never a victim
+const MVV_LVA_RANKS: [i32; 6] = [6, 5, 2, 3, 4, 1];
+
+const TT_MOVE_SCORE: i32 = i32::MAX;
+const GOOD_CAPTURE_SCORE: i32 = 3_000_000;
+const QUIET_PROMOTION_SCORE: i32 = 2_500_000;
+const KILLER_SCORE: i32 = 2_000_000;
+const COUNTERMOVE_SCORE: i32 = 1_000_000;
+const BAD_CAPTURE_SCORE: i32 = -3_000_000;
+// history scores stay within +-HISTORY_MAX, well inside the bands above
+pub const HISTORY_MAX: i32 = 16384;
+
+const fn piece_value(piece: ChessPiece) -> i32 {
+    SEE_VALUES[cp_index(piece) % 6]
+}
+
+const fn promotion_value(chess_move: ChessMove) -> i32 {
+    match chess_move.move_type() {
+        MoveType::Promotion(piece_type) => SEE_VALUES[cp_index((Side::White, piece_type))] - SEE_VALUES[cpt_index!(P)],
+        _ => 0,
+    }
+}
+
+const fn is_capture(chessboard: &ChessBoard, chess_move: ChessMove) -> bool {
+    chessboard.mailbox[chess_move.target()].is_some() || matches!(chess_move.move_type(), MoveType::EnPassant)
+}
+
+// both sides' pieces attacking square, given a custom occupancy
+bmi2_const_fn! {
+    fn attackers_to(chessboard: &ChessBoard, square: Square, occupied: BitBoard) -> BitBoard {
+        let piece_bbs = &chessboard.piece_bbs;
+        let queens = piece_bbs[cpt_index!(Q)].bit_or(&piece_bbs[cpt_index!(q)]);
+        let diagonals = queens.bit_or(&piece_bbs[cpt_index!(B)]).bit_or(&piece_bbs[cpt_index!(b)]);
+        let laterals = queens.bit_or(&piece_bbs[cpt_index!(R)]).bit_or(&piece_bbs[cpt_index!(r)]);
+        let knights = piece_bbs[cpt_index!(N)].bit_or(&piece_bbs[cpt_index!(n)]);
+        let kings = piece_bbs[cpt_index!(K)].bit_or(&piece_bbs[cpt_index!(k)]);
+        get_b_pawn_attack(square)
+            .bit_and(&piece_bbs[cpt_index!(P)])
+            .bit_or(&get_w_pawn_attack(square).bit_and(&piece_bbs[cpt_index!(p)]))
+            .bit_or(&get_knight_attack(square).bit_and(&knights))
+            .bit_or(&get_king_attack(square).bit_and(&kings))
+            .bit_or(&get_bishop_attack(square, occupied).bit_and(&diagonals))
+            .bit_or(&get_rook_attack(square, occupied).bit_and(&laterals))
+            .bit_and(&occupied)
+    }
+}
+
+// static exchange evaluation: material balance of the capture sequence on the target square,
+// with both sides always recapturing with their least valuable attacker (or standing pat)
+pub fn see(chessboard: &ChessBoard, chess_move: ChessMove) -> i32 {
+    let source = Square::new(chess_move.source() as u8);
+    let target = Square::new(chess_move.target() as u8);
+    let mut occupied = chessboard.blockers().pop_bit(source);
+    let mut gain: [i32; 32] = [0; 32];
+    gain[0] = match chessboard.mailbox[target.to_index()] {
+        Some(piece) => piece_value(piece),
+        None => 0,
+    };
+    if let MoveType::EnPassant = chess_move.move_type() {
+        gain[0] = SEE_VALUES[cpt_index!(P)];
+        let captured = match chessboard.side_to_move {
+            Side::White => target.to_index() - 8,
+            Side::Black => target.to_index() + 8,
+        };
+        occupied = occupied.pop_bit(Square::new(captured as u8));
+    }
+    gain[0] += promotion_value(chess_move);
+
+    // value of the piece now standing on target
+    let mut on_target = match (chessboard.mailbox[source.to_index()], chess_move.move_type()) {
+        (_, MoveType::Promotion(piece_type)) => SEE_VALUES[cp_index((Side::White, piece_type))],
+        (Some(piece), _) => piece_value(piece),
+        (None, _) => return 0,
+    };
+    let mut side = chessboard.side_to_move.update();
+    let mut depth: usize = 0;
+    while depth + 1 < gain.len() {
+        depth += 1;
+        // speculative, what side gains if it captures the piece on target
+        gain[depth] = on_target - gain[depth - 1];
+        // neither side can do better by continuing
+        if (-gain[depth - 1]).max(gain[depth]) < 0 {
+            break;
+        }
+        // removed attackers may uncover sliders behind them
+        let attackers = attackers_to(chessboard, target, occupied);
+        let offset = match side {
+            Side::White => 0,
+            Side::Black => 6,
+        };
+        // least valuable attacker, pawns first, the king last
+        let mut attacker: Option<(Square, usize)> = None;
+        for i in [5, 2, 3, 4, 1, 0] {
+            if let Some(square) = attackers.bit_and(&chessboard.piece_bbs[offset + i]).lsb_square() {
+                attacker = Some((square, i));
+                break;
+            }
+        }
+        match attacker {
+            Some((square, i)) => {
+                on_target = SEE_VALUES[i];
+                occupied = occupied.pop_bit(square);
+                side = side.update();
+            }
+            None => break,
+        }
+    }
+    // the last entry is speculative, the exchange stopped before it
+    while depth > 1 {
+        depth -= 1;
+        gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+    }
+    gain[0]
+}
+
+// 0 for moves that capture nothing, quiet promotions included
+pub const fn mvv_lva(chessboard: &ChessBoard, chess_move: ChessMove) -> i32 {
+    let victim = match (chessboard.mailbox[chess_move.target()], chess_move.move_type()) {
+        (Some(piece), _) => MVV_LVA_RANKS[cp_index(piece) % 6],
+        (None, MoveType::EnPassant) => MVV_LVA_RANKS[cpt_index!(P)],
+        (None, _) => return 0,
+    };
+    let attacker = match chessboard.mailbox[chess_move.source()] {
+        Some(piece) => MVV_LVA_RANKS[cp_index(piece) % 6],
+        None => 0,
+    };
+    victim * 8 - attacker
+}
+
+/* ==== search heuristics ==== */
+
+// killers, butterfly history and countermoves, kept across the nodes of a search
+pub struct MoveOrdering {
+    killers: [[Option<ChessMove>; 2]; MAX_PLY],
+    // [side][source][target]
+    history: [[[i32; 64]; 64]; 2],
+    // indexed by the source and target of the previous move
+    countermoves: [[Option<ChessMove>; 64]; 64],
+}
+
+impl Default for MoveOrdering {
+    fn default() -> Self {
+        MoveOrdering::new()
+    }
+}
+
+const fn side_index(side: Side) -> usize {
+    match side {
+        Side::White => 0,
+        Side::Black => 1,
+    }
+}
+
+impl MoveOrdering {
+    pub const fn new() -> Self {
+        MoveOrdering { killers: [[None; 2]; MAX_PLY], history: [[[0; 64]; 64]; 2], countermoves: [[None; 64]; 64] }
+    }
+
+    pub fn clear(&mut self) {
+        *self = MoveOrdering::new();
+    }
+
+    pub fn killers(&self, ply: usize) -> [Option<ChessMove>; 2] {
+        self.killers[ply.min(MAX_PLY - 1)]
+    }
+
+    pub fn history(&self, side: Side, chess_move: ChessMove) -> i32 {
+        self.history[side_index(side)][chess_move.source()][chess_move.target()]
+    }
+
+    pub fn countermove(&self, previous: ChessMove) -> Option<ChessMove> {
+        self.countermoves[previous.source()][previous.target()]
+    }
+
+    // history gravity: scores saturate towards +-HISTORY_MAX instead of overflowing
+    fn update_history(&mut self, side: Side, chess_move: ChessMove, bonus: i32) {
+        let entry = &mut self.history[side_index(side)][chess_move.source()][chess_move.target()];
+        let bonus = bonus.clamp(-HISTORY_MAX, HISTORY_MAX);
+        *entry += bonus - *entry * bonus.abs() / HISTORY_MAX;
+    }
+
+    // call on a beta cutoff, quiets_tried are the quiet moves searched before best_move
+    pub fn record_cutoff(
+        &mut self,
+        chessboard: &ChessBoard,
+        ply: usize,
+        depth: u32,
+        best_move: ChessMove,
+        previous: Option<ChessMove>,
+        quiets_tried: &[ChessMove],
+    ) {
+        if is_capture(chessboard, best_move) {
+            return;
+        }
+        let ply = ply.min(MAX_PLY - 1);
+        if self.killers[ply][0] != Some(best_move) {
+            self.killers[ply][1] = self.killers[ply][0];
+            self.killers[ply][0] = Some(best_move);
+        }
+        let bonus = (depth * depth) as i32;
+        let side = chessboard.side_to_move;
+        self.update_history(side, best_move, bonus);
+        for &chess_move in quiets_tried {
+            if chess_move != best_move {
+                self.update_history(side, chess_move, -bonus);
+            }
+        }
+        if let Some(previous) = previous {
+            self.countermoves[previous.source()][previous.target()] = Some(best_move);
+        }
+    }
+
+    pub fn score(
+        &self,
+        chessboard: &ChessBoard,
+        chess_move: ChessMove,
+        tt_move: Option<ChessMove>,
+        ply: usize,
+        previous: Option<ChessMove>,
+    ) -> i32 {
+        if tt_move == Some(chess_move) {
+            return TT_MOVE_SCORE;
+        }
+        if is_capture(chessboard, chess_move) {
+            return match see(chessboard, chess_move) >= 0 {
+                true => GOOD_CAPTURE_SCORE + mvv_lva(chessboard, chess_move) + promotion_value(chess_move),
+                false => BAD_CAPTURE_SCORE + mvv_lva(chessboard, chess_move),
+            };
+        }
+        if matches!(chess_move.move_type(), MoveType::Promotion(_)) {
+            return match see(chessboard, chess_move) >= 0 {
+                true => QUIET_PROMOTION_SCORE + promotion_value(chess_move),
+                false => BAD_CAPTURE_SCORE + promotion_value(chess_move),
+            };
+        }
+        let killers = self.killers(ply);
+        if killers[0] == Some(chess_move) {
+            return KILLER_SCORE + 1;
+        }
+        if killers[1] == Some(chess_move) {
+            return KILLER_SCORE;
+        }
+        if previous.is_some_and(|x| self.countermove(x) == Some(chess_move)) {
+            return COUNTERMOVE_SCORE;
+        }
+        self.history(chessboard.side_to_move, chess_move)
+    }
+}
+
+/* ==== move picker ==== */
+
+// lazy selection sort: each call to next() moves the best remaining move to the front, so
+// a cutoff after the first few moves never pays for sorting the whole list
+pub struct MovePicker {
+    moves: Vec<(ChessMove, i32)>,
+    index: usize,
+}
+
+impl MovePicker {
+    pub fn new(
+        chessboard: &ChessBoard,
+        moves: Vec<ChessMove>,
+        ordering: &MoveOrdering,
+        tt_move: Option<ChessMove>,
+        ply: usize,
+        previous: Option<ChessMove>,
+    ) -> Self {
+        let moves = moves.into_iter().map(|x| (x, ordering.score(chessboard, x, tt_move, ply, previous))).collect();
+        MovePicker { moves, index: 0 }
+    }
+}
+
+impl Iterator for MovePicker {
+    type Item = (ChessMove, i32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.moves.len() {
+            return None;
+        }
+        let mut best = self.index;
+        for i in self.index + 1..self.moves.len() {
+            if self.moves[i].1 > self.moves[best].1 {
+                best = i;
+            }
+        }
+        self.moves.swap(self.index, best);
+        self.index += 1;
+        Some(self.moves[self.index - 1])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.moves.len() - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitboard::PieceType;
+
+    fn mv(source: &str, target: &str) -> ChessMove {
+        ChessMove::new(source.parse().unwrap(), target.parse().unwrap(), MoveType::Normal)
+    }
+
+    #[test]
+    fn static_exchange() {
+        // rook takes an undefended pawn
+        let chessboard = ChessBoard::from_fen("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1").unwrap();
+        assert_eq!(see(&chessboard, mv("e1", "e5")), 100);
+        // knight takes a pawn defended by a pawn, and x-rayed rook/queen batteries
+        let chessboard = ChessBoard::from_fen("1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1").unwrap();
+        assert_eq!(see(&chessboard, mv("d3", "e5")), -220);
+        // quiet move onto a square attacked by a pawn loses the piece
+        let chessboard = ChessBoard::from_fen("4k3/8/3p4/8/8/2B5/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(see(&chessboard, mv("c3", "d4")), 0);
+        assert_eq!(see(&chessboard, mv("c3", "e5")), -330);
+        // promotion on a defended square
+        let chessboard = ChessBoard::from_fen("3rk3/2P5/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let promotion =
+            ChessMove::new("c7".parse().unwrap(), "c8".parse().unwrap(), MoveType::Promotion(PieceType::Queen));
+        assert_eq!(see(&chessboard, promotion), -100);
+    }
+
+    #[test]
+    fn picker_order() {
+        let chessboard = ChessBoard::from_fen("4k3/8/2q3p1/1P5p/8/8/3r4/3QK3 w - - 0 1").unwrap();
+        let tt_move = mv("e1", "f1");
+        let pawn_takes_queen = mv("b5", "c6");
+        let queen_takes_rook = mv("d1", "d2");
+        let king_takes_rook = mv("e1", "d2");
+        let killer = mv("d1", "a4");
+        let quiet = mv("b5", "b6");
+        // the pawn on h5 is defended, taking it loses the queen
+        let bad_capture = mv("d1", "h5");
+        let mut ordering = MoveOrdering::new();
+        ordering.record_cutoff(&chessboard, 3, 4, killer, None, &[quiet]);
+        assert!(ordering.history(Side::White, killer) > 0);
+        assert!(ordering.history(Side::White, quiet) < 0);
+
+        let moves = vec![bad_capture, quiet, killer, king_takes_rook, queen_takes_rook, tt_move, pawn_takes_queen];
+        let picked: Vec<ChessMove> =
+            MovePicker::new(&chessboard, moves, &ordering, Some(tt_move), 3, None).map(|x| x.0).collect();
+        let expected = vec![tt_move, pawn_takes_queen, queen_takes_rook, king_takes_rook, killer, quiet, bad_capture];
+        assert_eq!(picked, expected);
+    }
+
+    #[test]
+    fn quiet_promotions() {
+        let chessboard = ChessBoard::from_fen("4k3/1P6/8/3p4/8/4N3/8/4K3 w - - 0 1").unwrap();
+        let promotion =
+            |piece| ChessMove::new("b7".parse().unwrap(), "b8".parse().unwrap(), MoveType::Promotion(piece));
+        let (queen, knight) = (promotion(PieceType::Queen), promotion(PieceType::Knight));
+        let capture = mv("e3", "d5");
+        let quiet = mv("e1", "f1");
+        // nothing is captured, so there is no victim
+        assert_eq!(mvv_lva(&chessboard, queen), 0);
+        let ordering = MoveOrdering::new();
+        let picked: Vec<ChessMove> =
+            MovePicker::new(&chessboard, vec![quiet, knight, queen, capture], &ordering, None, 0, None)
+                .map(|x| x.0)
+                .collect();
+        assert_eq!(picked, vec![capture, queen, knight, quiet]);
+    }
+}
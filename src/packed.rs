@@ -0,0 +1,287 @@
+use std::io::{self, Read, Write};
+
+use crate::ChessBoard;
+use crate::bitboard::*;
+use crate::error::ChessError;
+use crate::square::Square;
+
+/* ==== packed positions ==== */
+
+// a fixed 32-byte position record for training data, about a third of the size of fen:
+//  0..8   occupancy bitboard, little endian
+//  8..24  4-bit piece codes (cp_index) of the occupied squares, lowest square first, low nibble first
+//  24     flags, bits 0-3 castle_bools, bit 4 set if black is to move
+//  25     en passant square index, or 0xFF if there is none
+//  26..28 half move clock, little endian
+//  28..30 full move number, little endian
+//  30..32 reserved, zero
+
+pub const PACKED_SIZE: usize = 32;
+// a packed position, followed by the score (i16, little endian) and the result
+pub const RECORD_SIZE: usize = PACKED_SIZE + 3;
+
+const NO_ENPASSANT: u8 = 0xFF;
+const SIDE_FLAG: u8 = 0b10000;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PackedBoard {
+    data: [u8; PACKED_SIZE],
+}
+
+// game outcome from white's point of view
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GameResult {
+    BlackWin,
+    Draw,
+    WhiteWin,
+}
+
+impl GameResult {
+    pub const fn to_u8(&self) -> u8 {
+        match self {
+            GameResult::BlackWin => 0,
+            GameResult::Draw => 1,
+            GameResult::WhiteWin => 2,
+        }
+    }
+
+    pub const fn from_u8(data: u8) -> Option<GameResult> {
+        match data {
+            0 => Some(GameResult::BlackWin),
+            1 => Some(GameResult::Draw),
+            2 => Some(GameResult::WhiteWin),
+            _ => None,
+        }
+    }
+}
+
+impl PackedBoard {
+    pub const fn to_bytes(&self) -> [u8; PACKED_SIZE] {
+        self.data
+    }
+
+    // no checks here, decode() rejects malformed data
+    pub const fn from_bytes(data: [u8; PACKED_SIZE]) -> Self {
+        PackedBoard { data }
+    }
+
+    pub fn encode(chessboard: &ChessBoard) -> Result<PackedBoard, ChessError> {
+        let occupancy = chessboard.blockers();
+        if occupancy.count_ones() > 32 {
+            return Err(ChessError::InvalidEncoding("more than 32 pieces"));
+        }
+        let mut data: [u8; PACKED_SIZE] = [0; PACKED_SIZE];
+        data[0..8].copy_from_slice(&occupancy.to_u64().to_le_bytes());
+        for (i, square) in occupancy.iter().enumerate() {
+            let code = match chessboard.mailbox[square.to_index()] {
+                Some(piece) => cp_index(piece) as u8,
+                None => return Err(ChessError::InvalidEncoding("mailbox disagrees with bitboards")),
+            };
+            data[8 + i / 2] |= code << (4 * (i % 2));
+        }
+
+        let mut flags: u8 = 0;
+        for (i, can_castle) in chessboard.castle_bools.iter().enumerate() {
+            if *can_castle {
+                flags |= 1 << i;
+            }
+        }
+        if let Side::Black = chessboard.side_to_move {
+            flags |= SIDE_FLAG;
+        }
+        data[24] = flags;
+        data[25] = match chessboard.enpassant_bb.lsb_square() {
+            Some(square) => square.to_u8(),
+            None => NO_ENPASSANT,
+        };
+        data[26..28].copy_from_slice(&chessboard.half_move_clock.to_le_bytes());
+        data[28..30].copy_from_slice(&chessboard.full_move_number.to_le_bytes());
+        Ok(PackedBoard { data })
+    }
+
+    // rejects data that can't have come from encode(), use validate() to check the position itself
+    pub fn decode(&self) -> Result<ChessBoard, ChessError> {
+        let data = &self.data;
+        let occupancy = BitBoard::new(u64::from_le_bytes(data[0..8].try_into().unwrap()));
+        if occupancy.count_ones() > 32 {
+            return Err(ChessError::InvalidEncoding("more than 32 pieces"));
+        }
+        let mut piece_bbs: [BitBoard; 12] = [BitBoard::ZERO; 12];
+        let mut mailbox: [Option<ChessPiece>; 64] = [None; 64];
+        for (i, square) in occupancy.iter().enumerate() {
+            let code = ((data[8 + i / 2] >> (4 * (i % 2))) & 0xF) as usize;
+            if code >= 12 {
+                return Err(ChessError::InvalidEncoding("invalid piece code"));
+            }
+            piece_bbs[code] |= BitBoard::nth(square);
+            mailbox[square.to_index()] = Some(CHESS_PIECES[code]);
+        }
+
+        let flags = data[24];
+        if flags & !(SIDE_FLAG | 0b1111) != 0 {
+            return Err(ChessError::InvalidEncoding("invalid flags"));
+        }
+        let mut castle_bools: [bool; 4] = [false; 4];
+        for (i, can_castle) in castle_bools.iter_mut().enumerate() {
+            *can_castle = flags & (1 << i) != 0;
+        }
+        let side_to_move = match flags & SIDE_FLAG {
+            0 => Side::White,
+            _ => Side::Black,
+        };
+        let enpassant_bb = match data[25] {
+            NO_ENPASSANT => BitBoard::ZERO,
+            x if x < 64 => BitBoard::nth(Square::new(x)),
+            _ => return Err(ChessError::InvalidEncoding("invalid en passant square")),
+        };
+
+        let mut chessboard = ChessBoard {
+            piece_bbs,
+            mailbox,
+            castle_bools,
+            enpassant_bb,
+            check_bb: BitBoard::ZERO,
+            side_to_move,
+            half_move_clock: u16::from_le_bytes([data[26], data[27]]),
+            full_move_number: u16::from_le_bytes([data[28], data[29]]),
+        };
+        chessboard.check_bb = chessboard.compute_check_bb();
+        Ok(chessboard)
+    }
+}
+
+/* ==== record streams ==== */
+
+// writes (position, score, result) records of RECORD_SIZE bytes each
+pub struct PackedWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> PackedWriter<W> {
+    pub fn new(inner: W) -> Self {
+        PackedWriter { inner }
+    }
+
+    pub fn write(&mut self, chessboard: &ChessBoard, score: i16, result: GameResult) -> io::Result<()> {
+        let packed = PackedBoard::encode(chessboard).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let mut record: [u8; RECORD_SIZE] = [0; RECORD_SIZE];
+        record[..PACKED_SIZE].copy_from_slice(&packed.to_bytes());
+        record[PACKED_SIZE..PACKED_SIZE + 2].copy_from_slice(&score.to_le_bytes());
+        record[PACKED_SIZE + 2] = result.to_u8();
+        self.inner.write_all(&record)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+// reads records written by PackedWriter, stops cleanly at the end of the stream
+pub struct PackedReader<R: Read> {
+    inner: R,
+}
+
+impl<R: Read> PackedReader<R> {
+    pub fn new(inner: R) -> Self {
+        PackedReader { inner }
+    }
+
+    pub fn read(&mut self) -> io::Result<Option<(ChessBoard, i16, GameResult)>> {
+        let mut record: [u8; RECORD_SIZE] = [0; RECORD_SIZE];
+        // distinguish a clean end of stream from a truncated record
+        let mut filled: usize = 0;
+        while filled < RECORD_SIZE {
+            match self.inner.read(&mut record[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        match filled {
+            0 => return Ok(None),
+            RECORD_SIZE => {}
+            _ => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated packed record")),
+        }
+
+        let packed = PackedBoard::from_bytes(record[..PACKED_SIZE].try_into().unwrap());
+        let chessboard = packed.decode().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let score = i16::from_le_bytes([record[PACKED_SIZE], record[PACKED_SIZE + 1]]);
+        let result = match GameResult::from_u8(record[PACKED_SIZE + 2]) {
+            Some(x) => x,
+            None => return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid game result")),
+        };
+        Ok(Some((chessboard, score, result)))
+    }
+}
+
+impl<R: Read> Iterator for PackedReader<R> {
+    type Item = io::Result<(ChessBoard, i16, GameResult)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FENS: [&str; 4] = [
+        crate::fen::START_FEN,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2",
+        "4k3/8/8/8/8/8/4r3/4K3 w - - 57 300",
+    ];
+
+    #[test]
+    fn encode_decode_round_trip() {
+        for fen in FENS {
+            let chessboard = ChessBoard::from_fen(fen).unwrap();
+            let packed = PackedBoard::encode(&chessboard).unwrap();
+            assert_eq!(packed.decode(), Ok(chessboard), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn decode_rejects_malformed_data() {
+        let packed = PackedBoard::encode(&ChessBoard::start_pos()).unwrap().to_bytes();
+        let mut bad_piece = packed;
+        bad_piece[8] = 0xFF;
+        let mut bad_flags = packed;
+        bad_flags[24] = 0xFF;
+        let mut bad_enpassant = packed;
+        bad_enpassant[25] = 64;
+        for data in [bad_piece, bad_flags, bad_enpassant, [0xFF; PACKED_SIZE]] {
+            assert!(PackedBoard::from_bytes(data).decode().is_err());
+        }
+    }
+
+    #[test]
+    fn record_stream_round_trip() {
+        let results = [GameResult::WhiteWin, GameResult::Draw, GameResult::BlackWin, GameResult::Draw];
+        let mut writer = PackedWriter::new(Vec::new());
+        for (i, fen) in FENS.iter().enumerate() {
+            writer.write(&ChessBoard::from_fen(fen).unwrap(), i as i16 * 100 - 150, results[i]).unwrap();
+        }
+        let bytes = writer.into_inner();
+        assert_eq!(bytes.len(), FENS.len() * RECORD_SIZE);
+
+        let records: Vec<_> = PackedReader::new(bytes.as_slice()).collect::<io::Result<_>>().unwrap();
+        assert_eq!(records.len(), FENS.len());
+        for (i, (chessboard, score, result)) in records.into_iter().enumerate() {
+            assert_eq!(chessboard.to_fen(), FENS[i]);
+            assert_eq!(score, i as i16 * 100 - 150);
+            assert_eq!(result, results[i]);
+        }
+
+        // a truncated record is an error, not the end of the stream
+        let mut reader = PackedReader::new(&bytes[..RECORD_SIZE + 1]);
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_err());
+    }
+}
@@ -0,0 +1,171 @@
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::_pext_u64;
+
+use crate::bitboard::*;
+use crate::square::Square;
+
+/* ==== pext ==== */
+
+// pext(blockers, mask) is a perfect index into the relevant occupancies of a square, so the
+// tables below are dense (1 << popcount(mask) entries per square) and need no magic numbers.
+// lookups use bmi2 when the cpu supports it, and fall back to the magic tables otherwise.
+
+const BISHOP_PEXT_OFFSETS: [usize; 65] = pext_offsets(BISHOP_MBB_MASK);
+const ROOK_PEXT_OFFSETS: [usize; 65] = pext_offsets(ROOK_MBB_MASK);
+const BISHOP_PEXT_SIZE: usize = BISHOP_PEXT_OFFSETS[64];
+const ROOK_PEXT_SIZE: usize = ROOK_PEXT_OFFSETS[64];
+static BISHOP_ATTACKS_PEXT: [BitBoard; BISHOP_PEXT_SIZE] = bishop_attack_pext();
+static ROOK_ATTACKS_PEXT: [BitBoard; ROOK_PEXT_SIZE] = rook_attack_pext();
+
+// start of each square's slice in the dense table, the last entry is the total size
+const fn pext_offsets(masks: [BitBoard; 64]) -> [usize; 65] {
+    let mut offsets: [usize; 65] = [0; 65];
+    let mut i: usize = 0;
+    while i < 64 {
+        offsets[i + 1] = offsets[i] + (1 << masks[i].count_ones());
+        i += 1;
+    }
+    return offsets;
+}
+
+const fn bishop_attack_pext() -> [BitBoard; BISHOP_PEXT_SIZE] {
+    let mut attacks: [BitBoard; BISHOP_PEXT_SIZE] = [BitBoard::ZERO; BISHOP_PEXT_SIZE];
+    let mut i: usize = 0;
+    while i < 64 {
+        let mask = BISHOP_MBB_MASK[i].to_u64();
+        // carry-rippler: walks the subsets of mask in the same order as their pext index
+        let mut occ: u64 = 0;
+        let mut j: usize = BISHOP_PEXT_OFFSETS[i];
+        loop {
+            attacks[j] = magic_bishop_attack(Square::new(i as u8), BitBoard::new(occ));
+            occ = occ.wrapping_sub(mask) & mask;
+            j += 1;
+            if occ == 0 {
+                break;
+            }
+        }
+        i += 1;
+    }
+    return attacks;
+}
+
+const fn rook_attack_pext() -> [BitBoard; ROOK_PEXT_SIZE] {
+    let mut attacks: [BitBoard; ROOK_PEXT_SIZE] = [BitBoard::ZERO; ROOK_PEXT_SIZE];
+    let mut i: usize = 0;
+    while i < 64 {
+        let mask = ROOK_MBB_MASK[i].to_u64();
+        let mut occ: u64 = 0;
+        let mut j: usize = ROOK_PEXT_OFFSETS[i];
+        loop {
+            attacks[j] = magic_rook_attack(Square::new(i as u8), BitBoard::new(occ));
+            occ = occ.wrapping_sub(mask) & mask;
+            j += 1;
+            if occ == 0 {
+                break;
+            }
+        }
+        i += 1;
+    }
+    return attacks;
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "bmi2")]
+fn pext_bishop_attack(square: Square, blockers: BitBoard) -> BitBoard {
+    let i = square.to_index();
+    let index = _pext_u64(blockers.to_u64(), BISHOP_MBB_MASK[i].to_u64()) as usize;
+    BISHOP_ATTACKS_PEXT[BISHOP_PEXT_OFFSETS[i] + index]
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "bmi2")]
+fn pext_rook_attack(square: Square, blockers: BitBoard) -> BitBoard {
+    let i = square.to_index();
+    let index = _pext_u64(blockers.to_u64(), ROOK_MBB_MASK[i].to_u64()) as usize;
+    ROOK_ATTACKS_PEXT[ROOK_PEXT_OFFSETS[i] + index]
+}
+
+// the result is cached by std, so this is a single atomic load after the first call
+pub fn has_bmi2() -> bool {
+    #[cfg(target_arch = "x86_64")]
+    return std::arch::is_x86_feature_detected!("bmi2");
+    #[cfg(not(target_arch = "x86_64"))]
+    return false;
+}
+
+pub fn get_bishop_attack(square: Square, blockers: BitBoard) -> BitBoard {
+    #[cfg(target_arch = "x86_64")]
+    if has_bmi2() {
+        // safety: bmi2 support was checked above
+        return unsafe { pext_bishop_attack(square, blockers) };
+    }
+    magic_bishop_attack(square, blockers)
+}
+
+pub fn get_rook_attack(square: Square, blockers: BitBoard) -> BitBoard {
+    #[cfg(target_arch = "x86_64")]
+    if has_bmi2() {
+        // safety: bmi2 support was checked above
+        return unsafe { pext_rook_attack(square, blockers) };
+    }
+    magic_rook_attack(square, blockers)
+}
+
+pub fn get_queen_attack(square: Square, blockers: BitBoard) -> BitBoard {
+    get_bishop_attack(square, blockers).bit_or(&get_rook_attack(square, blockers))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hint::black_box;
+    use std::time::Instant;
+
+    use super::*;
+    use crate::magic::MagicRng;
+
+    #[test]
+    fn pext_attacks_match_magic() {
+        let mut rng = MagicRng::new(0x5EED);
+        for i in 0..64u8 {
+            let square = Square::new(i);
+            for _ in 0..4096 {
+                let blockers = BitBoard::new(rng.next_u64() & rng.next_u64());
+                assert_eq!(get_bishop_attack(square, blockers), magic_bishop_attack(square, blockers));
+                assert_eq!(get_rook_attack(square, blockers), magic_rook_attack(square, blockers));
+            }
+        }
+    }
+
+    // cargo test --release --features bmi2 bench_pext -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn bench_pext_against_magic() {
+        const LOOKUPS: usize = 1 << 20;
+        const ROUNDS: usize = 16;
+        let mut rng = MagicRng::new(0x5EED);
+        let queries: Vec<(Square, BitBoard)> = (0..LOOKUPS)
+            .map(|_| (Square::new(rng.next_u64() as u8), BitBoard::new(rng.next_u64() & rng.next_u64())))
+            .collect();
+
+        let start = Instant::now();
+        for _ in 0..ROUNDS {
+            for &(square, blockers) in queries.iter() {
+                let (square, blockers) = (black_box(square), black_box(blockers));
+                black_box(magic_bishop_attack(square, blockers).bit_or(&magic_rook_attack(square, blockers)));
+            }
+        }
+        let magic = start.elapsed();
+
+        let start = Instant::now();
+        for _ in 0..ROUNDS {
+            for &(square, blockers) in queries.iter() {
+                black_box(get_queen_attack(black_box(square), black_box(blockers)));
+            }
+        }
+        let pext = start.elapsed();
+
+        println!("bmi2 detected: {}", has_bmi2());
+        println!("magic: {:.2} ns/lookup", magic.as_nanos() as f64 / (LOOKUPS * ROUNDS) as f64);
+        println!("pext:  {:.2} ns/lookup", pext.as_nanos() as f64 / (LOOKUPS * ROUNDS) as f64);
+    }
+}
@@ -0,0 +1,89 @@
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::ChessBoard;
+use crate::chessmove::ChessMove;
+use crate::square::Square;
+
+/* ==== serde ==== */
+
+// Side and PieceType derive their impls, the types below use their compact text forms instead:
+// Square as "e4", ChessMove as its raw u16 (lossless, uci strings drop the move type),
+// and ChessBoard as fen. deserializing a ChessBoard also validates it.
+
+impl Serialize for Square {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Square {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for ChessMove {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u16(self.to_u16())
+    }
+}
+
+impl<'de> Deserialize<'de> for ChessMove {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(ChessMove::from_u16(u16::deserialize(deserializer)?))
+    }
+}
+
+impl Serialize for ChessBoard {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_fen())
+    }
+}
+
+impl<'de> Deserialize<'de> for ChessBoard {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fen = String::deserialize(deserializer)?;
+        let chessboard = ChessBoard::from_fen(&fen).map_err(D::Error::custom)?;
+        match chessboard.validate() {
+            Ok(()) => Ok(chessboard),
+            Err(errors) => Err(D::Error::custom(errors[0])),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitboard::{PieceType, Side};
+    use crate::chessmove::MoveType;
+
+    #[test]
+    fn json_round_trip() {
+        let square: Square = "e4".parse().unwrap();
+        assert_eq!(serde_json::to_string(&square).unwrap(), "\"e4\"");
+        assert_eq!(serde_json::from_str::<Square>("\"e4\"").unwrap(), square);
+        assert!(serde_json::from_str::<Square>("\"e9\"").is_err());
+
+        let chess_move =
+            ChessMove::new("e7".parse().unwrap(), "e8".parse().unwrap(), MoveType::Promotion(PieceType::Queen));
+        let json = serde_json::to_string(&chess_move).unwrap();
+        assert_eq!(serde_json::from_str::<ChessMove>(&json).unwrap(), chess_move);
+
+        assert_eq!(serde_json::to_string(&Side::Black).unwrap(), "\"Black\"");
+        assert_eq!(serde_json::from_str::<PieceType>("\"Knight\"").unwrap(), PieceType::Knight);
+
+        let chessboard = ChessBoard::start_pos();
+        let json = serde_json::to_string(&chessboard).unwrap();
+        assert_eq!(json, format!("\"{}\"", crate::fen::START_FEN));
+        assert_eq!(serde_json::from_str::<ChessBoard>(&json).unwrap(), chessboard);
+    }
+
+    #[test]
+    fn rejects_invalid_positions() {
+        assert!(serde_json::from_str::<ChessBoard>("\"not a fen\"").is_err());
+        // white to move, but black is in check
+        assert!(serde_json::from_str::<ChessBoard>("\"4k3/8/8/8/8/8/4R3/4K3 w - - 0 1\"").is_err());
+    }
+}
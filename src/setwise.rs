@@ -0,0 +1,182 @@
+use crate::bitboard::*;
+
+/* ==== set-wise attack generation ==== */
+
+// kogge-stone fills compute the attacks of a whole set of sliders at once, instead of looping
+// over get_rook_attack/get_bishop_attack square by square. empty is the set of empty squares.
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Direction {
+    North,
+    South,
+    East,
+    West,
+    NorthEast,
+    NorthWest,
+    SouthEast,
+    SouthWest,
+}
+
+impl Direction {
+    pub const ROOK: [Direction; 4] = [Direction::North, Direction::South, Direction::East, Direction::West];
+    pub const BISHOP: [Direction; 4] =
+        [Direction::NorthEast, Direction::NorthWest, Direction::SouthEast, Direction::SouthWest];
+
+    // index offset of a single step, note: east is towards the h-file, i.e. towards bit 0
+    const fn delta(&self) -> i32 {
+        match self {
+            Direction::North => 8,
+            Direction::South => -8,
+            Direction::East => -1,
+            Direction::West => 1,
+            Direction::NorthEast => 7,
+            Direction::NorthWest => 9,
+            Direction::SouthEast => -9,
+            Direction::SouthWest => -7,
+        }
+    }
+
+    // squares a step in this direction may land on without wrapping around the board
+    const fn wrap_mask(&self) -> u64 {
+        match self {
+            Direction::North | Direction::South => u64::MAX,
+            Direction::East | Direction::NorthEast | Direction::SouthEast => !COL_MASKS[7].to_u64(),
+            Direction::West | Direction::NorthWest | Direction::SouthWest => !COL_MASKS[0].to_u64(),
+        }
+    }
+}
+
+const fn shift(data: u64, delta: i32) -> u64 {
+    if delta > 0 { data << delta } else { data >> -delta }
+}
+
+// sliders plus every square reachable from them in direction dir through empty squares
+pub const fn occluded_fill(sliders: BitBoard, empty: BitBoard, dir: Direction) -> BitBoard {
+    let delta = dir.delta();
+    let mut fill = sliders.to_u64();
+    let mut pro = empty.to_u64() & dir.wrap_mask();
+    fill |= pro & shift(fill, delta);
+    pro &= shift(pro, delta);
+    fill |= pro & shift(fill, 2 * delta);
+    pro &= shift(pro, 2 * delta);
+    fill |= pro & shift(fill, 4 * delta);
+    BitBoard::new(fill)
+}
+
+// like occluded_fill, but one step further (so the first blocker is included) and without the sliders
+pub const fn sliding_attacks(sliders: BitBoard, empty: BitBoard, dir: Direction) -> BitBoard {
+    let fill = occluded_fill(sliders, empty, dir).to_u64();
+    BitBoard::new(shift(fill, dir.delta()) & dir.wrap_mask())
+}
+
+pub const fn rook_attacks_setwise(rooks: BitBoard, empty: BitBoard) -> BitBoard {
+    let mut data: u64 = 0u64;
+    let mut i: usize = 0;
+    while i < 4 {
+        data |= sliding_attacks(rooks, empty, Direction::ROOK[i]).to_u64();
+        i += 1;
+    }
+    BitBoard::new(data)
+}
+
+pub const fn bishop_attacks_setwise(bishops: BitBoard, empty: BitBoard) -> BitBoard {
+    let mut data: u64 = 0u64;
+    let mut i: usize = 0;
+    while i < 4 {
+        data |= sliding_attacks(bishops, empty, Direction::BISHOP[i]).to_u64();
+        i += 1;
+    }
+    BitBoard::new(data)
+}
+
+pub const fn queen_attacks_setwise(queens: BitBoard, empty: BitBoard) -> BitBoard {
+    rook_attacks_setwise(queens, empty).bit_or(&bishop_attacks_setwise(queens, empty))
+}
+
+pub const fn knight_attacks_setwise(knights: BitBoard) -> BitBoard {
+    let data = knights.to_u64();
+    let not_h = !COL_MASKS[0].to_u64();
+    let not_gh = !(COL_MASKS[0].to_u64() | COL_MASKS[1].to_u64());
+    let not_a = !COL_MASKS[7].to_u64();
+    let not_ab = !(COL_MASKS[7].to_u64() | COL_MASKS[6].to_u64());
+    let attacks = ((data << 17) & not_h)
+        | ((data << 15) & not_a)
+        | ((data << 10) & not_gh)
+        | ((data << 6) & not_ab)
+        | ((data >> 6) & not_gh)
+        | ((data >> 10) & not_ab)
+        | ((data >> 15) & not_h)
+        | ((data >> 17) & not_a);
+    BitBoard::new(attacks)
+}
+
+pub const fn king_attacks_setwise(kings: BitBoard) -> BitBoard {
+    let sideways = kings.east().bit_or(&kings.west());
+    let row = sideways.bit_or(&kings);
+    sideways.bit_or(&row.north()).bit_or(&row.south())
+}
+
+pub const fn pawn_attacks_setwise(pawns: BitBoard, side: Side) -> BitBoard {
+    match side {
+        Side::White => pawns.north_east().bit_or(&pawns.north_west()),
+        Side::Black => pawns.south_east().bit_or(&pawns.south_west()),
+    }
+}
+
+// single pawn pushes onto empty squares
+pub const fn pawn_pushes_setwise(pawns: BitBoard, empty: BitBoard, side: Side) -> BitBoard {
+    match side {
+        Side::White => pawns.north().bit_and(&empty),
+        Side::Black => pawns.south().bit_and(&empty),
+    }
+}
+
+// double pawn pushes, both the passed-over and the target square must be empty
+pub const fn pawn_double_pushes_setwise(pawns: BitBoard, empty: BitBoard, side: Side) -> BitBoard {
+    let single = pawn_pushes_setwise(pawns, empty, side);
+    match side {
+        Side::White => pawn_pushes_setwise(single.bit_and(&ROW_MASKS[2]), empty, side),
+        Side::Black => pawn_pushes_setwise(single.bit_and(&ROW_MASKS[5]), empty, side),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::magic::MagicRng;
+    use crate::square::Square;
+
+    // union of the per-square lookups, for comparison
+    fn per_square(pieces: BitBoard, attack: impl Fn(Square) -> BitBoard) -> BitBoard {
+        pieces.into_iter().fold(BitBoard::ZERO, |acc, square| acc.bit_or(&attack(square)))
+    }
+
+    #[test]
+    fn setwise_attacks_match_per_square_lookups() {
+        let mut rng = MagicRng::new(0x5E7);
+        for _ in 0..10_000 {
+            let blockers = BitBoard::new(rng.next_u64() & rng.next_u64());
+            let empty = blockers.bit_not();
+            let pieces = BitBoard::new(rng.next_u64() & rng.next_u64() & rng.next_u64());
+            assert_eq!(rook_attacks_setwise(pieces, empty), per_square(pieces, |s| get_rook_attack(s, blockers)));
+            assert_eq!(bishop_attacks_setwise(pieces, empty), per_square(pieces, |s| get_bishop_attack(s, blockers)));
+            assert_eq!(knight_attacks_setwise(pieces), per_square(pieces, get_knight_attack));
+            assert_eq!(king_attacks_setwise(pieces), per_square(pieces, get_king_attack));
+            assert_eq!(pawn_attacks_setwise(pieces, Side::White), per_square(pieces, get_w_pawn_attack));
+            assert_eq!(pawn_attacks_setwise(pieces, Side::Black), per_square(pieces, get_b_pawn_attack));
+        }
+    }
+
+    #[test]
+    fn pawn_pushes() {
+        let pawns = ROW_MASKS[1].bit_or(&ROW_MASKS[6]);
+        let empty = ROW_MASKS[2].bit_or(&ROW_MASKS[3]).bit_or(&ROW_MASKS[4]).bit_or(&ROW_MASKS[5]);
+        assert_eq!(pawn_pushes_setwise(pawns, empty, Side::White), ROW_MASKS[2]);
+        assert_eq!(pawn_double_pushes_setwise(pawns, empty, Side::White), ROW_MASKS[3]);
+        assert_eq!(pawn_pushes_setwise(pawns, empty, Side::Black), ROW_MASKS[5]);
+        assert_eq!(pawn_double_pushes_setwise(pawns, empty, Side::Black), ROW_MASKS[4]);
+        // a blocked passed-over square prevents the double push
+        let empty = empty.pop_bit(Square::new(2 * 8 + 3));
+        assert_eq!(pawn_double_pushes_setwise(pawns, empty, Side::White), ROW_MASKS[3].pop_bit(Square::new(3 * 8 + 3)));
+    }
+}
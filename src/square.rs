@@ -1,6 +1,8 @@
-use std::ops::Range;
+use std::fmt::Display;
+use std::str::FromStr;
 
-use crate::bitboard::BitBoard;
+use crate::bitboard::SQUARE_SYM;
+use crate::error::ChessError;
 
 // atlernative: enum all 64 squares, and to index do this
 // make the enum `#[repr(u8)]`, then just cast it `as u8 as usize`
@@ -44,16 +46,23 @@ impl Square {
     pub(crate) const B_QUEENSIDE_CASTLE_SQUARE: Square = Square { data: 61 };
 }
 
-const SQUARES_RAW: [u8; 64] = [
-    00, 01, 02, 03, 04, 05, 06, 07, //
-    08, 09, 10, 11, 12, 13, 14, 15, //
-    16, 17, 18, 19, 20, 21, 22, 23, //
-    24, 25, 26, 27, 28, 29, 30, 31, //
-    32, 33, 34, 35, 36, 37, 38, 39, //
-    40, 41, 42, 43, 44, 45, 46, 47, //
-    48, 49, 50, 51, 52, 53, 54, 55, //
-    56, 57, 58, 59, 60, 61, 62, 63, //
-];
+// algebraic notation, e.g. "e4"
+impl Display for Square {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", SQUARE_SYM[self.to_index()])
+    }
+}
+
+impl FromStr for Square {
+    type Err = ChessError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match SQUARE_SYM.iter().position(|x| *x == s) {
+            Some(i) => Ok(Square::new(i as u8)),
+            None => Err(ChessError::InvalidSquare),
+        }
+    }
+}
 
 type Sq = Square;
 #[rustfmt::skip]
@@ -67,3 +76,9 @@ const SQUARES: [Square; 64] = [
     Sq{data : 48}, Sq{data : 49}, Sq{data : 50}, Sq{data : 51}, Sq{data : 52}, Sq{data : 53}, Sq{data : 54}, Sq{data : 55}, //
     Sq{data : 56}, Sq{data : 57}, Sq{data : 58}, Sq{data : 59}, Sq{data : 60}, Sq{data : 61}, Sq{data : 62}, Sq{data : 63}, //
 ];
+
+// test shorthand, e.g. sq("e4")
+#[cfg(test)]
+pub(crate) fn sq(name: &str) -> Square {
+    name.parse().unwrap()
+}
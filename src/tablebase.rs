@@ -0,0 +1,400 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::ChessBoard;
+use crate::bitboard::*;
+use crate::square::Square;
+
+/* ==== pawnless three-man tablebases ==== */
+
+// distance to mate tables for KQK and KRK, generated by retrograde iteration on the king and
+// slider attack tables, so no move generator is needed. the strong king is mirrored (files, ranks
+// and the h1-a8 diagonal) into the triangle h1, g1-g2, f1-f3, e1-e4, so a table has
+// 2 * 10 * 64 * 64 = 81920 entries, one byte each: 0 for a draw or an illegal position,
+// otherwise 1 + the number of plies to mate. colours don't matter without pawns, either side
+// can be the strong one.
+//
+// a strong king on the diagonal stays put under the diagonal mirror, so there the weak king (or,
+// if it is on the diagonal too, the piece) is mirrored below it instead. the slots of the mirrored
+// halves are never used and stay 0, 2 * (6 * 64 * 64 + 4 * (28 * 64 + 8 * 36)) = 65792 entries are
+// live. the unused ones keep the index a plain product.
+//
+// file format: b"CBTB", the format version, cp_index of the white strong piece, the entries.
+//
+// probe_wdl() is the single entry point for all three-man endings: KPK comes from the bitbase in
+// kpk.rs, which is built into the crate, KQK and KRK from whichever tables the caller has loaded.
+
+pub const TABLE_SIZE: usize = 2 * TRIANGLE * 64 * 64;
+
+const MAGIC: [u8; 4] = *b"CBTB";
+const VERSION: u8 = 1;
+const TRIANGLE: usize = 10;
+// unpack() needs the squares of the triangle, in triangle index order
+const TRIANGLE_SQUARES: [usize; TRIANGLE] = [0, 8, 9, 16, 17, 18, 24, 25, 26, 27];
+
+// from the side to move's point of view, in plies
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Dtm {
+    Draw,
+    Win(u8),
+    Loss(u8),
+}
+
+impl Dtm {
+    pub const fn wdl(&self) -> Wdl {
+        match self {
+            Dtm::Draw => Wdl::Draw,
+            Dtm::Win(_) => Wdl::Win,
+            Dtm::Loss(_) => Wdl::Loss,
+        }
+    }
+}
+
+// from the side to move's point of view
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Wdl {
+    Win,
+    Draw,
+    Loss,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tablebase {
+    piece: PieceType,
+    dtm: Vec<u8>,
+}
+
+// applies the symmetry that brings the strong king into the triangle to all three squares, every
+// position and its mirror images end up on the same squares
+fn canonical(strong_king: Square, weak_king: Square, piece: Square) -> [usize; 3] {
+    let mut squares = [strong_king.to_index(), weak_king.to_index(), piece.to_index()];
+    if squares[0] / 8 > 3 {
+        squares = squares.map(|x| x ^ 56);
+    }
+    if squares[0] % 8 > 3 {
+        squares = squares.map(|x| x ^ 7);
+    }
+    // the first square off the diagonal decides, the strong king's if it isn't on it
+    let above_diagonal = squares.iter().map(|x| (x % 8).cmp(&(x / 8))).find(|x| x.is_ne());
+    if above_diagonal == Some(std::cmp::Ordering::Greater) {
+        squares = squares.map(|x| (x % 8) * 8 + x / 8);
+    }
+    squares
+}
+
+fn index(strong_to_move: bool, strong_king: Square, weak_king: Square, piece: Square) -> usize {
+    let [strong_king, weak_king, piece] = canonical(strong_king, weak_king, piece);
+    let (row, col) = (strong_king / 8, strong_king % 8);
+    let triangle = row * (row + 1) / 2 + col;
+    (((strong_to_move as usize) * TRIANGLE + triangle) * 64 + weak_king) * 64 + piece
+}
+
+// inverse of index() for the slots it uses, the strong king is always in the triangle
+fn unpack(i: usize) -> (bool, Square, Square, Square) {
+    let strong_king = TRIANGLE_SQUARES[(i / (64 * 64)) % TRIANGLE];
+    let (weak_king, piece) = ((i / 64) % 64, i % 64);
+    (
+        i / (TRIANGLE * 64 * 64) == 1,
+        Square::new(strong_king as u8),
+        Square::new(weak_king as u8),
+        Square::new(piece as u8),
+    )
+}
+
+fn piece_attack(piece: PieceType, square: Square, blockers: BitBoard) -> BitBoard {
+    match piece {
+        PieceType::Queen => get_queen_attack(square, blockers),
+        _ => get_rook_attack(square, blockers),
+    }
+}
+
+// the side to move can't take the other king
+fn is_legal(piece: PieceType, strong_to_move: bool, strong_king: Square, weak_king: Square, square: Square) -> bool {
+    strong_king != weak_king
+        && strong_king != square
+        && weak_king != square
+        && get_king_attack(strong_king).nth_is_zero(weak_king)
+        && (strong_to_move == false || piece_attack(piece, square, BitBoard::nth(strong_king)).nth_is_zero(weak_king))
+}
+
+// indices of the positions the strong side can move to
+fn strong_moves(piece: PieceType, strong_king: Square, weak_king: Square, square: Square) -> Vec<usize> {
+    let king_moves = get_king_attack(strong_king) & !get_king_attack(weak_king) & !BitBoard::nth(square);
+    let blockers = BitBoard::nth(strong_king) | BitBoard::nth(weak_king);
+    let piece_moves = piece_attack(piece, square, blockers) & !blockers;
+    king_moves
+        .iter()
+        .map(|x| index(false, x, weak_king, square))
+        .chain(piece_moves.iter().map(|x| index(false, strong_king, weak_king, x)))
+        .collect()
+}
+
+// indices of the positions the weak side can move to, None if it can take the piece (a draw)
+fn weak_moves(piece: PieceType, strong_king: Square, weak_king: Square, square: Square) -> Option<Vec<usize>> {
+    let targets = get_king_attack(weak_king) & !get_king_attack(strong_king) & !BitBoard::nth(strong_king);
+    if targets.nth_is_not_zero(square) {
+        return None;
+    }
+    // the weak king no longer blocks the slider once it has moved
+    let safe = targets
+        .iter()
+        .filter(|&x| piece_attack(piece, square, BitBoard::nth(strong_king) | BitBoard::nth(x)).nth_is_zero(x));
+    Some(safe.map(|x| index(true, strong_king, x, square)).collect())
+}
+
+impl Tablebase {
+    // retrograde iteration: mates first, then every pass resolves the positions one ply further
+    // from mate, alternating between the strong and the weak side. whatever is left is a draw
+    pub fn generate(piece: PieceType) -> Tablebase {
+        assert!(
+            matches!(piece, PieceType::Queen | PieceType::Rook),
+            "Tablebase::generate error: only queen and rook tables are supported!"
+        );
+        let mut dtm: Vec<u8> = vec![0; TABLE_SIZE];
+        let mut plies: usize = 0;
+        loop {
+            let strong_to_move = plies % 2 == 1;
+            let start = (strong_to_move as usize) * TABLE_SIZE / 2;
+            let mut changed = false;
+            for i in start..start + TABLE_SIZE / 2 {
+                let (_, strong_king, weak_king, square) = unpack(i);
+                if dtm[i] != 0
+                    || index(strong_to_move, strong_king, weak_king, square) != i
+                    || is_legal(piece, strong_to_move, strong_king, weak_king, square) == false
+                {
+                    continue;
+                }
+                let resolved = match strong_to_move {
+                    true => strong_moves(piece, strong_king, weak_king, square).iter().any(|&x| dtm[x] != 0),
+                    false => match weak_moves(piece, strong_king, weak_king, square) {
+                        None => false,
+                        // checkmate, or stalemate
+                        Some(moves) if moves.is_empty() => {
+                            piece_attack(piece, square, BitBoard::nth(strong_king)).nth_is_not_zero(weak_king)
+                        }
+                        Some(moves) => moves.iter().all(|&x| dtm[x] != 0),
+                    },
+                };
+                if resolved {
+                    dtm[i] = plies as u8 + 1;
+                    changed = true;
+                }
+            }
+            if changed == false {
+                break;
+            }
+            plies += 1;
+        }
+        Tablebase { piece, dtm }
+    }
+
+    pub fn piece(&self) -> PieceType {
+        self.piece
+    }
+
+    // illegal positions read as a draw
+    pub fn probe_squares(&self, strong_king: Square, piece: Square, weak_king: Square, strong_to_move: bool) -> Dtm {
+        let i = index(strong_to_move, strong_king, weak_king, piece);
+        match (self.dtm[i], strong_to_move) {
+            (0, _) => Dtm::Draw,
+            (x, true) => Dtm::Win(x - 1),
+            (x, false) => Dtm::Loss(x - 1),
+        }
+    }
+
+    // None unless the position is two kings and one piece of this table's type
+    pub fn probe(&self, chessboard: &ChessBoard) -> Option<Dtm> {
+        if chessboard.blockers().count_ones() != 3 {
+            return None;
+        }
+        for side in [Side::White, Side::Black] {
+            let piece = chessboard.piece_bbs[cp_index((side, self.piece))];
+            let strong_king = chessboard.piece_bbs[cp_index((side, PieceType::King))];
+            let weak_king = chessboard.piece_bbs[cp_index((side.update(), PieceType::King))];
+            if let (Some(piece), Some(strong_king), Some(weak_king)) =
+                (piece.lsb_square(), strong_king.lsb_square(), weak_king.lsb_square())
+            {
+                return Some(self.probe_squares(strong_king, piece, weak_king, chessboard.side_to_move == side));
+            }
+        }
+        None
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&[VERSION, cp_index((Side::White, self.piece)) as u8])?;
+        writer.write_all(&self.dtm)
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Tablebase> {
+        let mut header: [u8; 6] = [0; 6];
+        reader.read_exact(&mut header)?;
+        if header[0..4] != MAGIC || header[4] != VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a chessbb tablebase"));
+        }
+        let piece = match header[5] {
+            1 => PieceType::Queen,
+            4 => PieceType::Rook,
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "unsupported tablebase piece")),
+        };
+        let mut dtm: Vec<u8> = vec![0; TABLE_SIZE];
+        reader.read_exact(&mut dtm)?;
+        Ok(Tablebase { piece, dtm })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Tablebase> {
+        Tablebase::read(BufReader::new(File::open(path)?))
+    }
+}
+
+// None if neither the KPK bitbase nor one of tablebases covers the position
+pub fn probe_wdl(tablebases: &[Tablebase], chessboard: &ChessBoard) -> Option<Wdl> {
+    if let Some(wdl) = probe_kpk(chessboard) {
+        return Some(wdl);
+    }
+    tablebases.iter().find_map(|x| x.probe(chessboard)).map(|x| x.wdl())
+}
+
+fn probe_kpk(chessboard: &ChessBoard) -> Option<Wdl> {
+    if chessboard.blockers().count_ones() != 3 {
+        return None;
+    }
+    for side in [Side::White, Side::Black] {
+        let pawn = chessboard.piece_bbs[cp_index((side, PieceType::Pawn))];
+        let strong_king = chessboard.piece_bbs[cp_index((side, PieceType::King))];
+        let weak_king = chessboard.piece_bbs[cp_index((side.update(), PieceType::King))];
+        if let (Some(pawn), Some(strong_king), Some(weak_king)) =
+            (pawn.lsb_square(), strong_king.lsb_square(), weak_king.lsb_square())
+        {
+            // the bitbase has the pawn moving up the board, so a black pawn is mirrored onto white's side
+            let (flip, strong_to_move) = (matches!(side, Side::Black) as u8 * 56, chessboard.side_to_move == side);
+            let mirror = |x: Square| Square::new(x.to_u8() ^ flip);
+            let side_to_move = if strong_to_move { Side::White } else { Side::Black };
+            let win = crate::kpk::probe(mirror(strong_king), mirror(pawn), mirror(weak_king), side_to_move)?;
+            return Some(match (win, strong_to_move) {
+                (false, _) => Wdl::Draw,
+                (true, true) => Wdl::Win,
+                (true, false) => Wdl::Loss,
+            });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::OnceLock;
+
+    use super::*;
+
+    // both tables, generated once for all tests
+    fn tables() -> &'static [Tablebase; 2] {
+        static TABLES: OnceLock<[Tablebase; 2]> = OnceLock::new();
+        TABLES.get_or_init(|| [Tablebase::generate(PieceType::Queen), Tablebase::generate(PieceType::Rook)])
+    }
+
+    fn probe(tablebase: &Tablebase, fen: &str) -> Option<Dtm> {
+        tablebase.probe(&ChessBoard::from_fen(fen).unwrap())
+    }
+
+    // the 8 symmetries of the board, from the BitBoard geometry helpers
+    fn images(square: Square) -> Vec<Square> {
+        let board = BitBoard::nth(square);
+        let transposed = board.rotate_clockwise().mirror_horizontal();
+        [board, transposed]
+            .map(|x| [x, x.flip_vertical(), x.mirror_horizontal(), x.rotate_180()].map(|x| x.lsb_square().unwrap()))
+            .concat()
+    }
+
+    #[test]
+    fn index_round_trip() {
+        let mut live: usize = 0;
+        for i in 0..TABLE_SIZE {
+            let (strong_to_move, strong_king, weak_king, square) = unpack(i);
+            let j = index(strong_to_move, strong_king, weak_king, square);
+            // an unused slot maps to the live slot of its mirror image
+            let (_, strong_king, weak_king, square) = unpack(j);
+            assert_eq!(index(strong_to_move, strong_king, weak_king, square), j);
+            live += (j == i) as usize;
+        }
+        assert_eq!(live, 65792);
+    }
+
+    #[test]
+    fn mirror_images_share_an_index() {
+        let images: Vec<Vec<Square>> = Square::iterator().map(|x| images(*x)).collect();
+        for (strong_king, weak_king, piece) in (0..64 * 64 * 64).map(|x| (x / 4096, (x / 64) % 64, x % 64)) {
+            let i = index(true, Square::new(strong_king as u8), Square::new(weak_king as u8), Square::new(piece as u8));
+            let mirrored = images[strong_king].iter().zip(&images[weak_king]).zip(&images[piece]);
+            for ((strong_king, weak_king), piece) in mirrored {
+                assert_eq!(index(true, *strong_king, *weak_king, *piece), i, "{} {} {}", strong_king, weak_king, piece);
+            }
+        }
+    }
+
+    #[test]
+    fn longest_mates() {
+        // the known maxima: KQK is won in at most 10 moves, KRK in at most 16
+        for (tablebase, moves) in tables().iter().zip([10, 16]) {
+            let longest = (TABLE_SIZE / 2..TABLE_SIZE).map(|i| tablebase.dtm[i]).max().unwrap();
+            assert_eq!(longest as usize - 1, 2 * moves - 1, "{:?}", tablebase.piece());
+        }
+    }
+
+    #[test]
+    fn known_positions() {
+        let [kqk, krk] = tables();
+        assert_eq!(probe(kqk, "k7/1Q6/1K6/8/8/8/8/8 b - - 0 1"), Some(Dtm::Loss(0)));
+        assert_eq!(probe(kqk, "k7/8/1K6/8/8/8/7Q/8 w - - 0 1"), Some(Dtm::Win(1)));
+        assert_eq!(probe(krk, "k7/8/1K6/8/8/8/8/7R w - - 0 1"), Some(Dtm::Win(1)));
+        // the same mate with colours swapped, and mirrored
+        assert_eq!(probe(krk, "K7/8/1k6/8/8/8/8/7r b - - 0 1"), Some(Dtm::Win(1)));
+        assert_eq!(probe(krk, "7k/8/6K1/8/8/8/8/R7 w - - 0 1"), Some(Dtm::Win(1)));
+        // stalemate, and the weak king taking an undefended piece
+        assert_eq!(probe(kqk, "k7/8/1Q6/8/8/8/8/7K b - - 0 1"), Some(Dtm::Draw));
+        assert_eq!(probe(kqk, "k7/1Q6/8/8/8/8/8/7K b - - 0 1"), Some(Dtm::Draw));
+        // the defending king can't take a protected rook, so this is stalemate, but it loses in the centre
+        assert_eq!(probe(krk, "k7/1R6/2K5/8/8/8/8/8 b - - 0 1"), Some(Dtm::Draw));
+        assert!(matches!(probe(krk, "8/8/8/4k3/3R4/2K5/8/8 b - - 0 1"), Some(Dtm::Loss(_))));
+        // wrong material
+        assert_eq!(probe(krk, "k7/8/1K6/8/8/8/8/7Q w - - 0 1"), None);
+        assert_eq!(probe(kqk, "k7/8/1K6/8/8/8/8/6QQ w - - 0 1"), None);
+    }
+
+    #[test]
+    fn wdl_probes() {
+        let wdl = |fen: &str| probe_wdl(tables(), &ChessBoard::from_fen(fen).unwrap());
+        // KPK, with either colour as the strong side
+        assert_eq!(wdl("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1"), Some(Wdl::Win));
+        assert_eq!(wdl("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1"), Some(Wdl::Loss));
+        assert_eq!(wdl("8/8/8/8/4p3/4k3/8/4K3 b - - 0 1"), Some(Wdl::Win));
+        assert_eq!(wdl("8/8/8/8/4p3/4k3/8/4K3 w - - 0 1"), Some(Wdl::Loss));
+        assert_eq!(wdl("k7/8/1K6/P7/8/8/8/8 w - - 0 1"), Some(Wdl::Draw));
+        // KQK and KRK from the tables
+        assert_eq!(wdl("k7/1Q6/1K6/8/8/8/8/8 b - - 0 1"), Some(Wdl::Loss));
+        assert_eq!(wdl("k7/8/1K6/8/8/8/8/7R w - - 0 1"), Some(Wdl::Win));
+        assert_eq!(probe_wdl(&[], &ChessBoard::from_fen("k7/8/1K6/8/8/8/8/7R w - - 0 1").unwrap()), None);
+        // no table for these
+        assert_eq!(wdl("k7/8/1K6/8/8/8/8/7B w - - 0 1"), None);
+        assert_eq!(wdl("k7/8/1K6/8/8/8/P7/7P w - - 0 1"), None);
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let [kqk, _] = tables();
+        let mut bytes = Vec::new();
+        kqk.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 6 + TABLE_SIZE);
+        assert_eq!(&Tablebase::read(bytes.as_slice()).unwrap(), kqk);
+        assert_eq!(Tablebase::read(&bytes[..100]).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        bytes[0] = b'X';
+        assert_eq!(Tablebase::read(bytes.as_slice()).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,278 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use crate::bitboard::Side;
+use crate::chessmove::ChessMove;
+
+/* ==== time management ==== */
+
+// turns the uci go parameters into a soft limit (don't start another iteration) and a hard limit
+// (abort the search), all times are in milliseconds. the soft limit is rescaled after every
+// iteration by best move stability, score drops and the number of legal moves.
+
+// reserved per move for gui/network lag
+pub const MOVE_OVERHEAD: u64 = 30;
+// moves the remaining time is spread over when there is no movestogo
+pub const SUDDEN_DEATH_MOVES: u64 = 30;
+
+// soft limit scale in percent, by the number of iterations the best move has been unchanged,
+// the first entry applies right after the best move changed
+const STABILITY_SCALE: [u64; 5] = [250, 120, 90, 80, 75];
+// at most this much extra time (in percent) for a falling score, reached at a 100cp drop
+const MAX_SCORE_DROP_SCALE: u64 = 100;
+// a forced move gets this percentage of the soft limit
+const FORCED_MOVE_SCALE: u64 = 10;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct SearchLimits {
+    pub wtime: Option<u64>,
+    pub btime: Option<u64>,
+    pub winc: u64,
+    pub binc: u64,
+    pub movestogo: Option<u64>,
+    pub movetime: Option<u64>,
+    pub nodes: Option<u64>,
+    pub depth: Option<u32>,
+    pub infinite: bool,
+}
+
+// the wall clock, or a deterministic clock that converts searched nodes to time
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Clock {
+    Wall(Instant),
+    Nodes { nodes_per_ms: u64 },
+}
+
+#[derive(Debug, Clone)]
+pub struct TimeManager {
+    clock: Clock,
+    base_soft: Option<u64>,
+    soft: Option<u64>,
+    hard: Option<u64>,
+    node_limit: Option<u64>,
+    stop: Arc<AtomicBool>,
+    best_move: Option<ChessMove>,
+    stability: usize,
+    best_score: Option<i32>,
+}
+
+impl TimeManager {
+    pub fn new(limits: &SearchLimits, side: Side, stop: Arc<AtomicBool>) -> Self {
+        TimeManager::with_clock(limits, side, stop, Clock::Wall(Instant::now()))
+    }
+
+    // reproducible searches: the same limits and node counts always stop at the same point
+    pub fn with_node_clock(limits: &SearchLimits, side: Side, stop: Arc<AtomicBool>, nodes_per_ms: u64) -> Self {
+        TimeManager::with_clock(limits, side, stop, Clock::Nodes { nodes_per_ms: nodes_per_ms.max(1) })
+    }
+
+    fn with_clock(limits: &SearchLimits, side: Side, stop: Arc<AtomicBool>, clock: Clock) -> Self {
+        let (time, inc) = match side {
+            Side::White => (limits.wtime, limits.winc),
+            Side::Black => (limits.btime, limits.binc),
+        };
+        let (soft, hard) = match (limits.infinite, limits.movetime, time) {
+            (true, _, _) => (None, None),
+            (false, Some(movetime), _) => {
+                let limit = movetime.saturating_sub(MOVE_OVERHEAD).max(1);
+                (Some(limit), Some(limit))
+            }
+            (false, None, Some(time)) => {
+                let available = time.saturating_sub(MOVE_OVERHEAD).max(1);
+                let moves = limits.movestogo.unwrap_or(SUDDEN_DEATH_MOVES).max(1);
+                // never plan to use more than 3/4 of the clock on a single move
+                let cap = (available * 3 / 4).max(1);
+                let soft = (available / moves + inc * 3 / 4).min(cap);
+                let hard = match moves {
+                    1 => soft,
+                    _ => (soft * 4).min(cap),
+                };
+                (Some(soft), Some(hard))
+            }
+            (false, None, None) => (None, None),
+        };
+        TimeManager {
+            clock,
+            base_soft: soft,
+            soft,
+            hard,
+            node_limit: limits.nodes,
+            stop,
+            best_move: None,
+            stability: 0,
+            best_score: None,
+        }
+    }
+
+    pub fn soft_limit(&self) -> Option<u64> {
+        self.soft
+    }
+
+    pub fn hard_limit(&self) -> Option<u64> {
+        self.hard
+    }
+
+    // elapsed milliseconds, nodes is only used by the node clock
+    pub fn elapsed(&self, nodes: u64) -> u64 {
+        match self.clock {
+            Clock::Wall(start) => start.elapsed().as_millis() as u64,
+            Clock::Nodes { nodes_per_ms } => nodes / nodes_per_ms,
+        }
+    }
+
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    // polled inside the search, raises the shared stop flag once the hard limit is hit
+    pub fn should_stop(&self, nodes: u64) -> bool {
+        if self.is_stopped() {
+            return true;
+        }
+        let out_of_nodes = self.node_limit.is_some_and(|x| nodes >= x);
+        let out_of_time = self.hard.is_some_and(|x| self.elapsed(nodes) >= x);
+        if out_of_nodes || out_of_time {
+            self.stop();
+            return true;
+        }
+        false
+    }
+
+    // checked between iterations of iterative deepening
+    pub fn should_start_iteration(&self, nodes: u64) -> bool {
+        if self.should_stop(nodes) {
+            return false;
+        }
+        match self.soft {
+            Some(soft) => self.elapsed(nodes) < soft,
+            None => true,
+        }
+    }
+
+    // call after every completed iteration
+    pub fn update(&mut self, best_move: ChessMove, score: i32, legal_moves: usize) {
+        // nothing to be stable relative to after the first iteration, so leave the limit alone
+        let stability_scale = match self.best_move {
+            None => 100,
+            Some(previous) => {
+                match previous == best_move {
+                    true => self.stability = (self.stability + 1).min(STABILITY_SCALE.len() - 1),
+                    false => self.stability = 0,
+                }
+                STABILITY_SCALE[self.stability]
+            }
+        };
+        self.best_move = Some(best_move);
+
+        let drop = match self.best_score {
+            Some(previous) => (previous - score).clamp(0, MAX_SCORE_DROP_SCALE as i32) as u64,
+            None => 0,
+        };
+        self.best_score = Some(score);
+
+        let (base_soft, hard) = match (self.base_soft, self.hard) {
+            (Some(base_soft), Some(hard)) => (base_soft, hard),
+            _ => return,
+        };
+        let mut soft = base_soft * stability_scale / 100;
+        soft = soft * (100 + drop) / 100;
+        if legal_moves == 1 {
+            soft = soft * FORCED_MOVE_SCALE / 100;
+        }
+        self.soft = Some(soft.clamp(1, hard));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MoveType;
+    use crate::square::sq;
+
+    fn flag() -> Arc<AtomicBool> {
+        Arc::new(AtomicBool::new(false))
+    }
+
+    fn e2e4() -> ChessMove {
+        ChessMove::new(sq("e2"), sq("e4"), MoveType::Normal)
+    }
+
+    fn d2d4() -> ChessMove {
+        ChessMove::new(sq("d2"), sq("d4"), MoveType::Normal)
+    }
+
+    #[test]
+    fn allocation() {
+        // sudden death with increment, from black's clock
+        let limits = SearchLimits { wtime: Some(1000), btime: Some(60_030), binc: 1000, ..Default::default() };
+        let tm = TimeManager::new(&limits, Side::Black, flag());
+        assert_eq!(tm.soft_limit(), Some(60_000 / 30 + 750));
+        assert_eq!(tm.hard_limit(), Some((60_000 / 30 + 750) * 4));
+
+        // the last move before the time control may use most of the clock, but never all of it
+        let limits = SearchLimits { wtime: Some(10_030), movestogo: Some(1), ..Default::default() };
+        let tm = TimeManager::new(&limits, Side::White, flag());
+        assert_eq!(tm.soft_limit(), Some(7500));
+        assert_eq!(tm.hard_limit(), Some(7500));
+
+        let limits = SearchLimits { movetime: Some(1000), wtime: Some(5), ..Default::default() };
+        let tm = TimeManager::new(&limits, Side::White, flag());
+        assert_eq!(tm.hard_limit(), Some(970));
+
+        let limits = SearchLimits { wtime: Some(1000), infinite: true, ..Default::default() };
+        let tm = TimeManager::new(&limits, Side::White, flag());
+        assert_eq!(tm.hard_limit(), None);
+    }
+
+    #[test]
+    fn node_clock_is_deterministic() {
+        let limits = SearchLimits { movetime: Some(130), ..Default::default() };
+        let stop = flag();
+        let tm = TimeManager::with_node_clock(&limits, Side::White, Arc::clone(&stop), 1000);
+        assert!(tm.should_start_iteration(50_000));
+        assert!(tm.should_stop(99_999) == false);
+        assert!(tm.should_stop(100_000));
+        // the stop flag is shared, e.g. with helper threads
+        assert!(stop.load(Ordering::Relaxed));
+        assert!(tm.should_stop(0));
+
+        let limits = SearchLimits { nodes: Some(5000), ..Default::default() };
+        let tm = TimeManager::new(&limits, Side::White, flag());
+        assert!(tm.should_stop(4999) == false);
+        assert!(tm.should_stop(5000));
+    }
+
+    #[test]
+    fn soft_limit_adjustments() {
+        let limits = SearchLimits { wtime: Some(30_030), ..Default::default() };
+        let mut tm = TimeManager::new(&limits, Side::White, flag());
+        let base = tm.soft_limit().unwrap();
+        // the first iteration has no previous best move to compare against
+        tm.update(d2d4(), 20, 20);
+        assert_eq!(tm.soft_limit(), Some(base));
+        // a changed best move is unstable
+        tm.update(e2e4(), 20, 20);
+        assert_eq!(tm.soft_limit(), Some(base * 250 / 100));
+        tm.update(e2e4(), 20, 20);
+        assert_eq!(tm.soft_limit(), Some(base * 120 / 100));
+        for _ in 0..10 {
+            tm.update(e2e4(), 20, 20);
+        }
+        assert_eq!(tm.soft_limit(), Some(base * 75 / 100));
+        // a falling score buys more time
+        tm.update(e2e4(), -80, 20);
+        assert_eq!(tm.soft_limit(), Some(base * 75 / 100 * 200 / 100));
+        // a forced move is played quickly
+        tm.update(e2e4(), -80, 1);
+        assert_eq!(tm.soft_limit(), Some(base * 75 / 100 * 10 / 100));
+    }
+}
@@ -0,0 +1,232 @@
+use std::fmt::Display;
+
+use super::*;
+
+/* ==== position validation ==== */
+
+// much of ChessBoard assumes a legal position (e.g. king_square panics if the king is missing),
+// validate() checks those assumptions up front and reports every violation it finds.
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PositionError {
+    KingCount { side: Side, count: u32 },
+    PawnOnBackRank(Square),
+    OverlappingPieces(Square),
+    MailboxMismatch(Square),
+    CastleRights { side: Side, kingside: bool },
+    EnPassant(BitBoard),
+    OpponentInCheck,
+    CheckersMismatch { expected: BitBoard, found: BitBoard },
+    PieceCount(Side),
+}
+
+impl Display for PositionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PositionError::KingCount { side, count } => write!(f, "{:?} has {} kings", side, count),
+            PositionError::PawnOnBackRank(square) => write!(f, "pawn on back rank at {}", SQUARE_SYM[square.to_index()]),
+            PositionError::OverlappingPieces(square) => {
+                write!(f, "more than one piece on {}", SQUARE_SYM[square.to_index()])
+            }
+            PositionError::MailboxMismatch(square) => {
+                write!(f, "mailbox and bitboards disagree on {}", SQUARE_SYM[square.to_index()])
+            }
+            PositionError::CastleRights { side, kingside } => match kingside {
+                true => write!(f, "{:?} can castle kingside, but king or rook has moved", side),
+                false => write!(f, "{:?} can castle queenside, but king or rook has moved", side),
+            },
+            PositionError::EnPassant(_) => write!(f, "implausible en passant square"),
+            PositionError::OpponentInCheck => write!(f, "side not to move is in check"),
+            PositionError::CheckersMismatch { .. } => write!(f, "check_bb does not match the checking pieces"),
+            PositionError::PieceCount(side) => write!(f, "{:?} has more pieces than promotions allow", side),
+        }
+    }
+}
+
+impl std::error::Error for PositionError {}
+
+// (castle_bools index, side, kingside, king square, rook square)
+const CASTLE_SQUARES: [(usize, Side, bool, u8, u8); 4] = [
+    (0, Side::White, true, 3, 0),
+    (1, Side::White, false, 3, 7),
+    (2, Side::Black, true, 59, 56),
+    (3, Side::Black, false, 59, 63),
+];
+
+impl ChessBoard {
+    pub fn validate(&self) -> Result<(), Vec<PositionError>> {
+        let mut errors: Vec<PositionError> = Vec::new();
+
+        // exactly one king per side
+        let mut kings_present = true;
+        for (side, i) in [(Side::White, cpt_index!(K)), (Side::Black, cpt_index!(k))] {
+            let count = self.piece_bbs[i].count_ones();
+            if count != 1 {
+                errors.push(PositionError::KingCount { side, count });
+                kings_present = false;
+            }
+        }
+
+        // no pawns on ranks 1 and 8
+        let back_ranks = ROW_MASKS[0].bit_or(&ROW_MASKS[7]);
+        let pawns = self.piece_bbs[cpt_index!(P)].bit_or(&self.piece_bbs[cpt_index!(p)]);
+        for square in pawns.bit_and(&back_ranks) {
+            errors.push(PositionError::PawnOnBackRank(square));
+        }
+
+        // piece_bbs are disjoint, and agree with mailbox
+        let mut seen = BitBoard::ZERO;
+        let mut overlaps = BitBoard::ZERO;
+        for bitboard in self.piece_bbs.iter() {
+            overlaps |= seen.bit_and(bitboard);
+            seen |= *bitboard;
+        }
+        for square in overlaps {
+            errors.push(PositionError::OverlappingPieces(square));
+        }
+        for &square in Square::iterator() {
+            if overlaps.nth_is_not_zero(square) {
+                continue;
+            }
+            let consistent = match self.mailbox[square.to_index()] {
+                Some(piece) => self.piece_bbs[cp_index(piece)].nth_is_not_zero(square),
+                None => seen.nth_is_zero(square),
+            };
+            if consistent == false {
+                errors.push(PositionError::MailboxMismatch(square));
+            }
+        }
+
+        // castling rights need the king and rook on their original squares
+        for (i, side, kingside, king, rook) in CASTLE_SQUARES {
+            let (king_index, rook_index) = match side {
+                Side::White => (cpt_index!(K), cpt_index!(R)),
+                Side::Black => (cpt_index!(k), cpt_index!(r)),
+            };
+            if self.castle_bools[i]
+                && (self.piece_bbs[king_index].nth_is_zero(Square::new(king))
+                    || self.piece_bbs[rook_index].nth_is_zero(Square::new(rook)))
+            {
+                errors.push(PositionError::CastleRights { side, kingside });
+            }
+        }
+
+        // en passant square must be behind an enemy pawn that just moved two squares
+        if self.enpassant_bb.is_not_zero() && self.is_enpassant_plausible() == false {
+            errors.push(PositionError::EnPassant(self.enpassant_bb));
+        }
+
+        // the remaining checks need both kings
+        if kings_present {
+            let (king_index, enemy_king_index) = match self.side_to_move {
+                Side::White => (cpt_index!(K), cpt_index!(k)),
+                Side::Black => (cpt_index!(k), cpt_index!(K)),
+            };
+            let enemy_king = self.piece_bbs[enemy_king_index].lsb_square().unwrap();
+            if self.attackers(enemy_king, self.side_to_move).is_not_zero() {
+                errors.push(PositionError::OpponentInCheck);
+            }
+            let king = self.piece_bbs[king_index].lsb_square().unwrap();
+            let expected = self.attackers(king, self.side_to_move.update());
+            if expected != self.check_bb {
+                errors.push(PositionError::CheckersMismatch { expected, found: self.check_bb });
+            }
+        }
+
+        // every piece beyond the initial set must have been a pawn
+        for (side, offset) in [(Side::White, 0), (Side::Black, 6)] {
+            let count = |i: usize| self.piece_bbs[offset + i].count_ones();
+            let promoted = count(1).saturating_sub(1)
+                + count(2).saturating_sub(2)
+                + count(3).saturating_sub(2)
+                + count(4).saturating_sub(2);
+            if count(5) + promoted > 8 {
+                errors.push(PositionError::PieceCount(side));
+            }
+        }
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+
+    fn is_enpassant_plausible(&self) -> bool {
+        if self.enpassant_bb.count_ones() != 1 {
+            return false;
+        }
+        let target = self.enpassant_bb.lsb_square().unwrap().to_index();
+        // (row of the target, square of the moved pawn, square the pawn came from)
+        let (row, pawn, origin, pawn_index) = match self.side_to_move {
+            Side::White => (5, target.wrapping_sub(8), target + 8, cpt_index!(p)),
+            Side::Black => (2, target + 8, target.wrapping_sub(8), cpt_index!(P)),
+        };
+        if ROWS[target] != row {
+            return false;
+        }
+        let blockers = self.blockers();
+        self.piece_bbs[pawn_index].nth_is_not_zero(Square::new(pawn as u8))
+            && blockers.nth_is_zero(Square::new(target as u8))
+            && blockers.nth_is_zero(Square::new(origin as u8))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::square::sq;
+
+    fn board(pieces: &[(&str, ChessPiece)], side_to_move: Side) -> ChessBoard {
+        let mut chessboard = ChessBoard::start_pos();
+        chessboard.piece_bbs = [BitBoard::ZERO; 12];
+        chessboard.mailbox = [None; 64];
+        chessboard.castle_bools = [false; 4];
+        chessboard.side_to_move = side_to_move;
+        for &(name, piece) in pieces {
+            chessboard.piece_bbs[cp_index(piece)] |= BitBoard::nth(sq(name));
+            chessboard.mailbox[sq(name).to_index()] = Some(piece);
+        }
+        chessboard
+    }
+
+    #[test]
+    fn start_pos_is_valid() {
+        assert_eq!(ChessBoard::start_pos().validate(), Ok(()));
+    }
+
+    #[test]
+    fn reports_every_violation() {
+        let mut chessboard = board(&[("e1", cpt!(K)), ("a8", cpt!(P)), ("e4", cpt!(r))], Side::White);
+        chessboard.castle_bools[0] = true;
+        let errors = chessboard.validate().unwrap_err();
+        assert!(errors.contains(&PositionError::KingCount { side: Side::Black, count: 0 }));
+        assert!(errors.contains(&PositionError::PawnOnBackRank(sq("a8"))));
+        assert!(errors.contains(&PositionError::CastleRights { side: Side::White, kingside: true }));
+    }
+
+    #[test]
+    fn checks_must_match_check_bb() {
+        let mut chessboard = board(&[("e1", cpt!(K)), ("e8", cpt!(k)), ("e4", cpt!(r))], Side::White);
+        assert_eq!(
+            chessboard.validate(),
+            Err(vec![PositionError::CheckersMismatch { expected: BitBoard::nth(sq("e4")), found: BitBoard::ZERO }])
+        );
+        chessboard.check_bb = BitBoard::nth(sq("e4"));
+        assert_eq!(chessboard.validate(), Ok(()));
+        chessboard.side_to_move = Side::Black;
+        chessboard.check_bb = BitBoard::ZERO;
+        assert_eq!(chessboard.validate(), Err(vec![PositionError::OpponentInCheck]));
+    }
+
+    #[test]
+    fn enpassant_and_mailbox() {
+        let mut chessboard = board(&[("e1", cpt!(K)), ("e8", cpt!(k)), ("d5", cpt!(p))], Side::White);
+        chessboard.enpassant_bb = BitBoard::nth(sq("d6"));
+        assert_eq!(chessboard.validate(), Ok(()));
+        chessboard.enpassant_bb = BitBoard::nth(sq("c6"));
+        assert_eq!(chessboard.validate(), Err(vec![PositionError::EnPassant(BitBoard::nth(sq("c6")))]));
+        chessboard.enpassant_bb = BitBoard::ZERO;
+        chessboard.mailbox[sq("d5").to_index()] = None;
+        assert_eq!(chessboard.validate(), Err(vec![PositionError::MailboxMismatch(sq("d5"))]));
+    }
+}
@@ -0,0 +1,52 @@
+use chessbb::{ChessError, ChessMove, MoveType, PieceType, Square};
+
+// only uses what the crate root exports, as a downstream crate would
+
+fn sq(name: &str) -> Square {
+    name.parse().unwrap()
+}
+
+#[test]
+fn build_and_inspect_moves() {
+    for (uci, source, target, move_type) in [
+        ("e2e4", "e2", "e4", MoveType::Normal),
+        ("e1g1", "e1", "g1", MoveType::Castle),
+        ("d5e6", "d5", "e6", MoveType::EnPassant),
+        ("e7e8q", "e7", "e8", MoveType::Promotion(PieceType::Queen)),
+        ("a2a1n", "a2", "a1", MoveType::Promotion(PieceType::Knight)),
+    ] {
+        let chessmove = ChessMove::new(sq(source), sq(target), move_type);
+        assert_eq!(chessmove.to_string(), uci);
+        assert_eq!(chessmove.move_type(), move_type);
+        assert_eq!(ChessMove::from_u16(chessmove.to_u16()), chessmove);
+    }
+}
+
+#[test]
+fn rejects_invalid_promotions() {
+    for piece in [PieceType::King, PieceType::Pawn] {
+        let result = ChessMove::try_new(sq("e7"), sq("e8"), MoveType::Promotion(piece));
+        assert_eq!(result, Err(ChessError::InvalidPromotion));
+    }
+    let chessmove = ChessMove::try_new(sq("e7"), sq("e8"), MoveType::Promotion(PieceType::Rook)).unwrap();
+    assert_eq!(chessmove.move_type(), MoveType::Promotion(PieceType::Rook));
+}
+
+#[test]
+fn setters_keep_the_other_fields() {
+    let mut chessmove = ChessMove::new(sq("e7"), sq("e8"), MoveType::Promotion(PieceType::Rook));
+    chessmove.set_source(sq("d7").to_index());
+    assert_eq!(chessmove.to_string(), "d7e8r");
+    chessmove.set_target(sq("c8").to_index());
+    assert_eq!(chessmove.to_string(), "d7c8r");
+    assert_eq!(chessmove.move_type(), MoveType::Promotion(PieceType::Rook));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn piece_type_serde_round_trip() {
+    for piece in [PieceType::Pawn, PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen, PieceType::King] {
+        let json = serde_json::to_string(&piece).unwrap();
+        assert_eq!(serde_json::from_str::<PieceType>(&json).unwrap(), piece);
+    }
+}